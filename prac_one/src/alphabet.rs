@@ -0,0 +1,110 @@
+use petgraph::prelude::DiGraph;
+
+use crate::nfa::{Edge, Kind};
+
+// symbols that no transition tells apart are grouped into one class, so the constructions only
+// have to look at one symbol per class. every class is a range of symbols between two places
+// where some edge starts or ends, and the dfa has one edge per class labelled with that range, so
+// the labels of its edges are the class table (the min dfa merges the ones it can't tell apart)
+#[derive(Debug, Clone)]
+pub struct Alphabet {
+    pub classes: Vec<Edge>, // in symbol order, no two overlap
+}
+
+impl Alphabet {
+    pub fn new<N>(graph: &DiGraph<N, Edge>) -> Self {
+        let bytes = graph.edge_weights().any(|e| e.is_byte());
+        let ranges = graph
+            .edge_weights()
+            .filter_map(|e| e.bounds())
+            .collect::<Vec<(u32, u32)>>();
+        // assertions look at the kind of the symbol, so kinds can't share a class
+        Self {
+            classes: pieces(&ranges, bytes, has_looks(graph)),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.classes.len()
+    }
+
+    // how many symbols there are in all the classes together
+    pub fn size(&self) -> u64 {
+        self.classes.iter().map(|c| c.size() as u64).sum()
+    }
+
+    // the symbol used to compute moves for the whole class
+    pub fn representative(&self, class: usize) -> Edge {
+        self.classes[class].first()
+    }
+
+    // the classes an edge reads, a dfa edge is exactly one class but an nfa edge can be several
+    pub fn classes_of(&self, edge: &Edge) -> Vec<usize> {
+        let Some((lo, hi)) = edge.bounds() else {
+            return Vec::new();
+        };
+        let first = self
+            .classes
+            .partition_point(|c| c.bounds().is_some_and(|(_, last)| last < lo));
+        (first..self.classes.len())
+            .take_while(|c| {
+                self.classes[*c]
+                    .bounds()
+                    .is_some_and(|(start, _)| start <= hi)
+            })
+            .collect()
+    }
+}
+
+// cuts the symbols up wherever one of the ranges starts or ends and gives back, in order, the
+// pieces that are in at least one of them. with kinds, a piece never mixes word, newline and
+// other symbols either
+pub fn pieces(ranges: &[(u32, u32)], bytes: bool, kinds: bool) -> Vec<Edge> {
+    let mut cuts = ranges
+        .iter()
+        .flat_map(|(lo, hi)| [*lo, *hi + 1])
+        .collect::<Vec<u32>>();
+    cuts.sort();
+    cuts.dedup();
+    // +1 where a range starts and -1 after it ends, so the running sum is how many cover a piece
+    let mut starts = vec![0i64; cuts.len()];
+    for (lo, hi) in ranges {
+        starts[cuts.binary_search(lo).unwrap()] += 1;
+        starts[cuts.binary_search(&(hi + 1)).unwrap()] -= 1;
+    }
+
+    let mut pieces = Vec::new();
+    let mut covered = 0;
+    for (i, window) in cuts.windows(2).enumerate() {
+        covered += starts[i];
+        if covered == 0 {
+            continue;
+        }
+        let (lo, hi) = (window[0], window[1] - 1);
+        if !kinds {
+            pieces.extend(Edge::from_bounds(lo, hi, bytes));
+            continue;
+        }
+        let mut from = lo;
+        let mut kind = None;
+        for symbol in Edge::from_bounds(lo, hi, bytes)
+            .iter()
+            .flat_map(|e| e.symbols())
+        {
+            let (at, _) = symbol.bounds().unwrap();
+            if kind.is_some_and(|k: Kind| k != symbol.kind()) {
+                pieces.extend(Edge::from_bounds(from, at - 1, bytes));
+                from = at;
+            }
+            kind = Some(symbol.kind());
+        }
+        pieces.extend(Edge::from_bounds(from, hi, bytes));
+    }
+    pieces
+}
+
+pub fn has_looks<N>(graph: &DiGraph<N, Edge>) -> bool {
+    graph
+        .edge_references()
+        .any(|e| matches!(e.weight(), Edge::Look(_)))
+}
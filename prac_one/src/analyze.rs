@@ -0,0 +1,117 @@
+use petgraph::{graph::NodeIndex, prelude::DiGraph, visit::EdgeRef};
+
+use crate::alphabet::Alphabet;
+use crate::lang::{count, live_states};
+use crate::nfa::{Edge, Weight};
+
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    pub empty: bool,
+    pub finite: Option<u128>, // number of accepted words when the language is finite
+    pub universal: bool,      // accepts every word over the symbols used in the dfa
+    pub unreachable: Vec<u32>,
+    pub dead: Vec<u32>,
+}
+
+// expects a dfa with its start at node 0, like the one from dfa::dfa
+pub fn analyze(dfa: &DiGraph<bool, Edge>) -> Analysis {
+    let reachable = reachable_states(dfa);
+    let live = live_states(dfa);
+
+    // a finite language has no cycle through states that are both reachable and live,
+    // so its longest word is shorter than the number of states
+    let finite = if has_cycle(dfa, |n| reachable[n] && live[n]) {
+        None
+    } else {
+        let counts = count(dfa, dfa.node_count());
+        Some(counts.iter().fold(0u128, |sum, c| sum.saturating_add(*c)))
+    };
+
+    // the edges out of a state never overlap, so their sizes add up to the symbols it reads
+    let symbols = Alphabet::new(dfa).size();
+    let universal = dfa
+        .node_indices()
+        .filter(|n| reachable[n.index()])
+        .all(|n| {
+            *dfa.node_weight(n).unwrap()
+                && dfa.edges(n).map(|e| e.weight().size() as u64).sum::<u64>() == symbols
+        });
+
+    Analysis {
+        empty: !live[0],
+        finite,
+        universal,
+        unreachable: unreachable_states(dfa),
+        dead: dead_states(dfa),
+    }
+}
+
+pub fn unreachable_states<N>(dfa: &DiGraph<N, Edge>) -> Vec<u32> {
+    let reachable = reachable_states(dfa);
+    dfa.node_indices()
+        .filter(|n| !reachable[n.index()])
+        .map(|n| n.index() as u32)
+        .collect::<Vec<u32>>()
+}
+
+// states that can never reach an accept state
+pub fn dead_states<N: Weight>(dfa: &DiGraph<N, Edge>) -> Vec<u32> {
+    let live = live_states(dfa);
+    dfa.node_indices()
+        .filter(|n| !live[n.index()])
+        .map(|n| n.index() as u32)
+        .collect::<Vec<u32>>()
+}
+
+pub fn reachable_states<N>(dfa: &DiGraph<N, Edge>) -> Vec<bool> {
+    let mut reachable = vec![false; dfa.node_count()];
+    if dfa.node_count() == 0 {
+        return reachable;
+    }
+    let mut stack = vec![NodeIndex::new(0)];
+    reachable[0] = true;
+    while let Some(state) = stack.pop() {
+        for e in dfa.edges(state) {
+            if !reachable[e.target().index()] {
+                reachable[e.target().index()] = true;
+                stack.push(e.target());
+            }
+        }
+    }
+    reachable
+}
+
+// depth first search for a back edge, only looking at the states keep lets through
+fn has_cycle(dfa: &DiGraph<bool, Edge>, keep: impl Fn(usize) -> bool) -> bool {
+    // 0 = not seen, 1 = on the current path, 2 = done
+    let mut colour = vec![0u8; dfa.node_count()];
+    for root in dfa.node_indices().filter(|n| keep(n.index())) {
+        if colour[root.index()] != 0 {
+            continue;
+        }
+        let mut stack = vec![(
+            root,
+            dfa.edges(root).map(|e| e.target()).collect::<Vec<_>>(),
+        )];
+        colour[root.index()] = 1;
+        while let Some((state, targets)) = stack.last_mut() {
+            match targets.pop() {
+                Some(target) if keep(target.index()) => match colour[target.index()] {
+                    0 => {
+                        colour[target.index()] = 1;
+                        let next = dfa.edges(target).map(|e| e.target()).collect::<Vec<_>>();
+                        stack.push((target, next));
+                    }
+                    1 => return true,
+                    _ => {}
+                },
+                Some(_) => {}
+                None => {
+                    colour[state.index()] = 2;
+                    stack.pop();
+                }
+            }
+        }
+    }
+    false
+}
@@ -0,0 +1,128 @@
+use std::fs;
+use std::path::Path;
+
+use prac_1::{dfa::Limits, to_xml, Dfa, Options};
+
+use crate::print_stats;
+
+// nfa, dfa and min dfa state counts, or why the pattern didn't compile
+type Counts = Result<(usize, usize, usize), String>;
+
+// compiles every pattern in the file to <out_dir>/<name>.xml and prints a summary table,
+// a .toml file maps names to patterns, anything else is one pattern per line
+// false when any of the patterns failed
+pub fn batch(
+    path: &Path,
+    out_dir: &Path,
+    options: Options,
+    limits: Limits,
+    stats: bool,
+) -> Result<bool, String> {
+    let source = fs::read_to_string(path).map_err(|e| format!("Unable to read file: {}", e))?;
+    let patterns = if path.extension().is_some_and(|e| e == "toml") {
+        from_toml(&source)?
+    } else {
+        from_lines(&source)
+    };
+    fs::create_dir_all(out_dir).map_err(|e| format!("Unable to create directory: {}", e))?;
+
+    let mut rows = Vec::<(String, Counts)>::new();
+    for (name, pattern) in patterns {
+        let counts = check_name(&name).and_then(|_| compile(&pattern, options, limits, stats));
+        let counts = counts.map(|(nfa, dfa, min)| {
+            let path = out_dir.join(format!("{}.xml", name));
+            fs::write(&path, to_xml(&min, "mindfa")).map(|_| (nfa, dfa, min.node_count()))
+        });
+        let counts = counts.and_then(|c| c.map_err(|e| format!("Unable to write file: {}", e)));
+        rows.push((name, counts));
+    }
+    print_summary(&rows);
+    Ok(rows.iter().all(|(_, counts)| counts.is_ok()))
+}
+
+// names end up as file names, they must not point anywhere outside of out_dir
+fn check_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        return Err(format!(
+            "Invalid name {:?}, it has to be a plain file name",
+            name
+        ));
+    }
+    Ok(())
+}
+
+fn compile(
+    pattern: &str,
+    options: Options,
+    limits: Limits,
+    stats: bool,
+) -> Result<(usize, usize, Dfa), String> {
+    let compiled = prac_1::compile_limited(pattern, options, limits)?;
+    if stats {
+        print_stats(pattern, &compiled.stats());
+    }
+    Ok((
+        compiled.nfa.node_count(),
+        compiled.dfa.node_count(),
+        compiled.min_dfa,
+    ))
+}
+
+// blank lines and lines starting with # are skipped, names are the line numbers
+fn from_lines(source: &str) -> Vec<(String, String)> {
+    source
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|(i, line)| (format!("line_{}", i + 1), line.to_owned()))
+        .collect::<Vec<(String, String)>>()
+}
+
+fn from_toml(source: &str) -> Result<Vec<(String, String)>, String> {
+    let table = source
+        .parse::<toml::Table>()
+        .map_err(|e| format!("Invalid toml: {}", e))?;
+    table
+        .into_iter()
+        .map(|(name, value)| match value {
+            toml::Value::String(pattern) => Ok((name, pattern)),
+            _ => Err(format!("Pattern {} is not a string", name)),
+        })
+        .collect::<Result<Vec<(String, String)>, String>>()
+}
+
+fn print_summary(rows: &[(String, Counts)]) {
+    let width = rows
+        .iter()
+        .map(|(name, _)| name.len())
+        .chain(["name".len()])
+        .max()
+        .unwrap();
+    println!(
+        "{:<width$}  {:>6}  {:>6}  {:>6}",
+        "name",
+        "nfa",
+        "dfa",
+        "min",
+        width = width
+    );
+    for (name, counts) in rows {
+        match counts {
+            Ok((nfa, dfa, min)) => println!(
+                "{:<width$}  {:>6}  {:>6}  {:>6}",
+                name,
+                nfa,
+                dfa,
+                min,
+                width = width
+            ),
+            // parse errors span a few lines, the last one says what's wrong
+            Err(e) => println!(
+                "{:<width$}  {}",
+                name,
+                e.lines().last().unwrap_or_default(),
+                width = width
+            ),
+        }
+    }
+}
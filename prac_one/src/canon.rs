@@ -0,0 +1,45 @@
+use std::collections::{HashMap, VecDeque};
+
+use petgraph::{graph::NodeIndex, prelude::DiGraph};
+
+use crate::lang::sorted_edges;
+use crate::nfa::{Edge, Weight};
+
+// renumbers the states in the order a breadth first search from the start finds them, taking
+// the edges of every state in symbol order, so equal dfas come out identical no matter how
+// their states were numbered before. states that can't be reached from the start are dropped
+pub fn canonical<N: Weight>(dfa: &DiGraph<N, Edge>) -> DiGraph<N, Edge> {
+    let mut canon = DiGraph::<N, Edge>::new();
+    if dfa.node_count() == 0 {
+        return canon;
+    }
+    let mut ids = HashMap::from([(0, canon.add_node(dfa[NodeIndex::new(0)]))]);
+    let mut queue = VecDeque::from([0]);
+    while let Some(state) = queue.pop_front() {
+        let from = ids[&state];
+        for (symbol, target) in sorted_edges(dfa, state) {
+            let to = *ids.entry(target).or_insert_with(|| {
+                queue.push_back(target);
+                canon.add_node(dfa[NodeIndex::new(target)])
+            });
+            canon.add_edge(from, to, symbol);
+        }
+    }
+
+    canon
+}
+
+// same automaton up to the numbering of the states (both with the start at node 0)
+pub fn isomorphic<N: Weight>(a: &DiGraph<N, Edge>, b: &DiGraph<N, Edge>) -> bool {
+    if a.node_count() != b.node_count() || a.edge_count() != b.edge_count() {
+        return false;
+    }
+    let count = a.node_count();
+    // states out of reach of the start would be dropped from the canonical forms
+    let (a, b) = (canonical(a), canonical(b));
+    a.node_count() == b.node_count()
+        && b.node_count() == count
+        && a.node_weights().eq(b.node_weights())
+        && a.node_indices()
+            .all(|n| sorted_edges(&a, n.index()) == sorted_edges(&b, n.index()))
+}
@@ -0,0 +1,136 @@
+use petgraph::{graph::NodeIndex, prelude::DiGraph, visit::EdgeRef};
+
+use crate::analyze::reachable_states;
+use crate::lang::live_states;
+use crate::nfa::{Edge, Look, Weight};
+
+// operations that build a new automaton out of existing ones (loaded with import::from_xml or
+// straight from the pipeline). they all give back an nfa with the start at node 0, which
+// dfa::dfa and min_dfa take like any other
+
+// the words of the automaton read backwards
+pub fn reverse(dfa: &DiGraph<bool, Edge>) -> DiGraph<bool, Edge> {
+    let mut nfa = DiGraph::<bool, Edge>::new();
+    let start = nfa.add_node(false);
+    // the old start is where the reversed words end
+    for n in dfa.node_indices() {
+        nfa.add_node(n.index() == 0);
+    }
+    for e in dfa.edge_references() {
+        nfa.add_edge(
+            shift(e.target(), 1),
+            shift(e.source(), 1),
+            backwards(*e.weight()),
+        );
+    }
+    for n in dfa.node_indices().filter(|n| dfa[*n]) {
+        nfa.add_edge(start, shift(n, 1), Edge::Epsilon);
+    }
+    nfa
+}
+
+// read backwards the start of the text (or a line) is where it ends, word boundaries look the
+// same from both sides
+fn backwards(edge: Edge) -> Edge {
+    match edge {
+        Edge::Look(Look::StartText) => Edge::Look(Look::EndText),
+        Edge::Look(Look::EndText) => Edge::Look(Look::StartText),
+        Edge::Look(Look::StartLine) => Edge::Look(Look::EndLine),
+        Edge::Look(Look::EndLine) => Edge::Look(Look::StartLine),
+        edge => edge,
+    }
+}
+
+// every prefix of an accepted word, so every state that can still reach an accept accepts
+pub fn prefix(dfa: &DiGraph<bool, Edge>) -> DiGraph<bool, Edge> {
+    let live = live_states(dfa);
+    dfa.map(|n, _| live[n.index()], |_, e| *e)
+}
+
+// every suffix of an accepted word, so the new start can jump to any state the old one reaches
+pub fn suffix(dfa: &DiGraph<bool, Edge>) -> DiGraph<bool, Edge> {
+    let mut nfa = DiGraph::<bool, Edge>::new();
+    let start = nfa.add_node(false);
+    copy(&mut nfa, dfa);
+    let reachable = reachable_states(dfa);
+    for n in dfa.node_indices().filter(|n| reachable[n.index()]) {
+        nfa.add_edge(start, shift(n, 1), Edge::Epsilon);
+    }
+    nfa
+}
+
+// a word of the first, then one of the second and so on
+pub fn concat(automata: &[DiGraph<bool, Edge>]) -> DiGraph<bool, Edge> {
+    let mut nfa = DiGraph::<bool, Edge>::new();
+    let start = nfa.add_node(false);
+    let mut ends = vec![start];
+    for automaton in automata {
+        let offset = copy(&mut nfa, automaton);
+        for end in ends {
+            nfa.add_edge(end, shift(NodeIndex::new(0), offset), Edge::Epsilon);
+        }
+        // only the accepts of the last one stay accepts
+        ends = accepts(automaton, offset);
+        for end in &ends {
+            nfa[*end] = false;
+        }
+    }
+    for end in ends {
+        nfa[end] = true;
+    }
+    nfa
+}
+
+// a word of any of them, the automata keep their weights (transducer.rs puts rules together this way)
+pub fn union<N: Weight>(automata: &[DiGraph<N, Edge>]) -> DiGraph<N, Edge> {
+    let mut nfa = DiGraph::<N, Edge>::new();
+    let start = nfa.add_node(N::default());
+    for automaton in automata {
+        let offset = copy(&mut nfa, automaton);
+        nfa.add_edge(start, shift(NodeIndex::new(0), offset), Edge::Epsilon);
+    }
+    nfa
+}
+
+// any number of words of any of them, (a|b|...)*
+pub fn star(automata: &[DiGraph<bool, Edge>]) -> DiGraph<bool, Edge> {
+    let mut nfa = DiGraph::<bool, Edge>::new();
+    let start = nfa.add_node(true);
+    for automaton in automata {
+        let offset = copy(&mut nfa, automaton);
+        nfa.add_edge(start, shift(NodeIndex::new(0), offset), Edge::Epsilon);
+        for end in accepts(automaton, offset) {
+            nfa[end] = false;
+            nfa.add_edge(end, start, Edge::Epsilon);
+        }
+    }
+    nfa
+}
+
+// adds every state and edge of from to graph, giving back what its node 0 was shifted by
+fn copy<N: Weight>(graph: &mut DiGraph<N, Edge>, from: &DiGraph<N, Edge>) -> usize {
+    let offset = graph.node_count();
+    for n in from.node_indices() {
+        graph.add_node(from[n]);
+    }
+    for e in from.edge_references() {
+        graph.add_edge(
+            shift(e.source(), offset),
+            shift(e.target(), offset),
+            *e.weight(),
+        );
+    }
+    offset
+}
+
+fn accepts(automaton: &DiGraph<bool, Edge>, offset: usize) -> Vec<NodeIndex> {
+    automaton
+        .node_indices()
+        .filter(|n| automaton[*n])
+        .map(|n| shift(n, offset))
+        .collect()
+}
+
+fn shift(n: NodeIndex, offset: usize) -> NodeIndex {
+    NodeIndex::new(n.index() + offset)
+}
@@ -0,0 +1,231 @@
+use petgraph::{prelude::DiGraph, visit::EdgeRef};
+
+use crate::alphabet::Alphabet;
+use crate::nfa::Edge;
+
+// state x class transition table in one flat little-endian buffer, the same layout is used in
+// memory and on disk so a serialized dfa can be matched against without copying it
+//
+//  magic "DDFA" | version | flags | states | classes | start | ranges
+//  ranges x (first symbol, last symbol, class)
+//  accept bitset, padded to 4 bytes
+//  states x classes next states, DEAD for no transition
+const MAGIC: &[u8; 4] = b"DDFA";
+const VERSION: u32 = 1;
+const HEADER: usize = 28;
+const BYTE_MODE: u32 = 1;
+const DEAD: u32 = u32::MAX;
+
+#[derive(Debug, Clone)]
+pub struct DenseDfa<B: AsRef<[u8]>> {
+    bytes: B,
+}
+
+impl DenseDfa<Vec<u8>> {
+    // expects start at node 0, which is what min_dfa gives back
+    pub fn new(min_dfa: &DiGraph<bool, Edge>) -> Self {
+        let alphabet = Alphabet::new(min_dfa);
+        let states = min_dfa.node_count();
+        let byte_mode = min_dfa.edge_weights().any(|e| e.is_byte());
+
+        // every class is one range of symbols already
+        let ranges = alphabet
+            .classes
+            .iter()
+            .enumerate()
+            .map(|(class, c)| {
+                let (lo, hi) = c.bounds().unwrap();
+                (lo, hi, class as u32)
+            })
+            .collect::<Vec<(u32, u32, u32)>>();
+
+        let mut table = vec![DEAD; states * alphabet.len()];
+        for e in min_dfa.edge_references() {
+            for class in alphabet.classes_of(e.weight()) {
+                table[e.source().index() * alphabet.len() + class] = e.target().index() as u32;
+            }
+        }
+
+        let mut bytes = Vec::<u8>::new();
+        bytes.extend_from_slice(MAGIC);
+        for field in [
+            VERSION,
+            if byte_mode { BYTE_MODE } else { 0 },
+            states as u32,
+            alphabet.len() as u32,
+            0,
+            ranges.len() as u32,
+        ] {
+            bytes.extend_from_slice(&field.to_le_bytes());
+        }
+        for (lo, hi, class) in ranges {
+            for field in [lo, hi, class] {
+                bytes.extend_from_slice(&field.to_le_bytes());
+            }
+        }
+        let mut accepts = vec![0u8; bitset_len(states)];
+        for n in min_dfa.node_indices() {
+            if *min_dfa.node_weight(n).unwrap() {
+                accepts[n.index() / 8] |= 1 << (n.index() % 8);
+            }
+        }
+        bytes.extend_from_slice(&accepts);
+        for next in table {
+            bytes.extend_from_slice(&next.to_le_bytes());
+        }
+
+        Self { bytes }
+    }
+}
+
+impl<'a> DenseDfa<&'a [u8]> {
+    // checks the header and every transition once, so matching never has to
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, &'static str> {
+        if bytes.len() < HEADER || &bytes[..4] != MAGIC {
+            return Err("Not a dense dfa");
+        }
+        let dfa = Self { bytes };
+        if dfa.field(0) != VERSION {
+            return Err("Unsupported dense dfa version");
+        }
+        let (states, classes) = (dfa.states() as u64, dfa.classes() as u64);
+        // the counts come straight from the file, a hostile header can make any of these overflow
+        let expected = (12 * dfa.ranges() as u64)
+            .checked_add(HEADER as u64)
+            .and_then(|n| n.checked_add(states.div_ceil(32) * 4))
+            .and_then(|n| n.checked_add(states.checked_mul(classes)?.checked_mul(4)?))
+            .ok_or("Dense dfa has the wrong length")?;
+        if bytes.len() as u64 != expected {
+            return Err("Dense dfa has the wrong length");
+        }
+        if states == 0 || dfa.start() as u64 >= states {
+            return Err("Dense dfa start state out of range");
+        }
+        // class() binary searches the ranges, so they have to be sorted and can't overlap
+        for i in 0..dfa.ranges() {
+            let (lo, hi, class) = dfa.range(i);
+            if lo > hi || (i > 0 && dfa.range(i - 1).1 >= lo) {
+                return Err("Dense dfa ranges are out of order");
+            }
+            if class as u64 >= classes {
+                return Err("Dense dfa class out of range");
+            }
+        }
+        for i in 0..(states * classes) as usize {
+            let next = dfa.read(dfa.table_offset() + 4 * i);
+            if next != DEAD && next as u64 >= states {
+                return Err("Dense dfa transition out of range");
+            }
+        }
+        Ok(dfa)
+    }
+}
+
+impl<B: AsRef<[u8]>> DenseDfa<B> {
+    pub fn as_bytes(&self) -> &[u8] {
+        self.bytes.as_ref()
+    }
+
+    pub fn is_match(&self, input: &str) -> bool {
+        if self.byte_mode() {
+            self.run(input.bytes().map(u32::from))
+        } else {
+            self.run(input.chars().map(u32::from))
+        }
+    }
+
+    pub fn is_match_bytes(&self, input: &[u8]) -> bool {
+        if self.byte_mode() {
+            self.run(input.iter().map(|b| u32::from(*b)))
+        } else {
+            match std::str::from_utf8(input) {
+                Ok(input) => self.run(input.chars().map(u32::from)),
+                Err(_) => false,
+            }
+        }
+    }
+
+    pub fn states(&self) -> u32 {
+        self.field(2)
+    }
+
+    pub fn classes(&self) -> u32 {
+        self.field(3)
+    }
+
+    fn run(&self, input: impl Iterator<Item = u32>) -> bool {
+        let mut state = self.start();
+        for symbol in input {
+            let class = match self.class(symbol) {
+                Some(class) => class,
+                None => return false,
+            };
+            let next = state as usize * self.classes() as usize + class as usize;
+            state = self.read(self.table_offset() + 4 * next);
+            if state == DEAD {
+                return false;
+            }
+        }
+        self.bytes.as_ref()[self.accepts_offset() + state as usize / 8] & (1 << (state % 8)) != 0
+    }
+
+    // binary search over the symbol ranges
+    fn class(&self, symbol: u32) -> Option<u32> {
+        let (mut lo, mut hi) = (0, self.ranges());
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            let (first, last, class) = self.range(mid);
+            if symbol < first {
+                hi = mid;
+            } else if symbol > last {
+                lo = mid + 1;
+            } else {
+                return Some(class);
+            }
+        }
+        None
+    }
+
+    fn byte_mode(&self) -> bool {
+        self.field(1) & BYTE_MODE != 0
+    }
+
+    fn start(&self) -> u32 {
+        self.field(4)
+    }
+
+    fn ranges(&self) -> usize {
+        self.field(5) as usize
+    }
+
+    fn range(&self, i: usize) -> (u32, u32, u32) {
+        let offset = HEADER + 12 * i;
+        (
+            self.read(offset),
+            self.read(offset + 4),
+            self.read(offset + 8),
+        )
+    }
+
+    fn accepts_offset(&self) -> usize {
+        HEADER + 12 * self.ranges()
+    }
+
+    fn table_offset(&self) -> usize {
+        self.accepts_offset() + bitset_len(self.states() as usize)
+    }
+
+    // header fields after the magic
+    fn field(&self, i: usize) -> u32 {
+        self.read(4 + 4 * i)
+    }
+
+    fn read(&self, offset: usize) -> u32 {
+        let bytes = &self.bytes.as_ref()[offset..offset + 4];
+        u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+}
+
+fn bitset_len(states: usize) -> usize {
+    states.div_ceil(32) * 4
+}
@@ -0,0 +1,333 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+use petgraph::prelude::DiGraph;
+use regex_syntax::hir::{self, Hir};
+
+use crate::alphabet::pieces;
+use crate::nfa::{class_byte_ranges, class_ranges, parse, Edge, Options};
+
+// regex -> dfa straight from Brzozowski derivatives, without going through an nfa.
+// the derivative of r by a symbol matches every w where a.w is matched by r, so the states of
+// the dfa are r and its derivatives. the smart constructors keep every Re in a normal form
+// (alternations sorted and deduplicated and so on), which is what makes there only be finitely
+// many of them, and it gives intersection and complement for free
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Re {
+    Empty,          // matches nothing
+    Epsilon,        // matches only the empty word
+    Set(Vec<Edge>), // one symbol out of sorted ranges that don't touch
+    Concat(Vec<Re>),
+    Alt(Vec<Re>),
+    And(Vec<Re>),
+    Star(Box<Re>),
+    Not(Box<Re>), // every word over the symbols of the expression that r doesn't match
+}
+
+impl Re {
+    pub fn set(symbols: Vec<Edge>) -> Re {
+        // ranges that overlap or touch are merged, so the same set is always written one way
+        let bytes = symbols.iter().any(|s| s.is_byte());
+        let mut bounds = symbols
+            .iter()
+            .filter_map(|s| s.bounds())
+            .collect::<Vec<(u32, u32)>>();
+        bounds.sort();
+        let mut merged = Vec::<(u32, u32)>::new();
+        for (lo, hi) in bounds {
+            match merged.last_mut() {
+                Some(last) if lo <= last.1 + 1 => last.1 = last.1.max(hi),
+                _ => merged.push((lo, hi)),
+            }
+        }
+        let symbols = merged
+            .into_iter()
+            .filter_map(|(lo, hi)| Edge::from_bounds(lo, hi, bytes))
+            .collect::<Vec<Edge>>();
+        if symbols.is_empty() {
+            Re::Empty
+        } else {
+            Re::Set(symbols)
+        }
+    }
+
+    pub fn concat(items: Vec<Re>) -> Re {
+        let mut flat = Vec::new();
+        for item in items {
+            match item {
+                Re::Empty => return Re::Empty,
+                Re::Epsilon => {}
+                Re::Concat(inner) => flat.extend(inner),
+                item => flat.push(item),
+            }
+        }
+        match flat.len() {
+            0 => Re::Epsilon,
+            1 => flat.pop().unwrap(),
+            _ => Re::Concat(flat),
+        }
+    }
+
+    pub fn alt(items: Vec<Re>) -> Re {
+        let mut flat = Vec::new();
+        for item in items {
+            match item {
+                Re::Empty => {}
+                Re::Alt(inner) => flat.extend(inner),
+                item => flat.push(item),
+            }
+        }
+        if flat.contains(&Re::universal()) {
+            return Re::universal();
+        }
+        flat.sort();
+        flat.dedup();
+        match flat.len() {
+            0 => Re::Empty,
+            1 => flat.pop().unwrap(),
+            _ => Re::Alt(flat),
+        }
+    }
+
+    pub fn and(items: Vec<Re>) -> Re {
+        let mut flat = Vec::new();
+        for item in items {
+            match item {
+                Re::Empty => return Re::Empty,
+                Re::And(inner) => flat.extend(inner),
+                item if item == Re::universal() => {}
+                item => flat.push(item),
+            }
+        }
+        flat.sort();
+        flat.dedup();
+        match flat.len() {
+            0 => Re::universal(),
+            1 => flat.pop().unwrap(),
+            _ => Re::And(flat),
+        }
+    }
+
+    pub fn star(re: Re) -> Re {
+        match re {
+            Re::Empty | Re::Epsilon => Re::Epsilon,
+            Re::Star(_) => re,
+            re => Re::Star(Box::new(re)),
+        }
+    }
+
+    pub fn complement(re: Re) -> Re {
+        match re {
+            Re::Not(inner) => *inner,
+            re => Re::Not(Box::new(re)),
+        }
+    }
+
+    // every word, as the complement of nothing
+    pub fn universal() -> Re {
+        Re::Not(Box::new(Re::Empty))
+    }
+
+    // whether the empty word is matched
+    pub fn nullable(&self) -> bool {
+        match self {
+            Re::Empty | Re::Set(_) => false,
+            Re::Epsilon | Re::Star(_) => true,
+            Re::Concat(items) | Re::And(items) => items.iter().all(|r| r.nullable()),
+            Re::Alt(items) => items.iter().any(|r| r.nullable()),
+            Re::Not(inner) => !inner.nullable(),
+        }
+    }
+
+    pub fn derive(&self, symbol: &Edge) -> Re {
+        match self {
+            Re::Empty | Re::Epsilon => Re::Empty,
+            Re::Set(symbols) if symbols.iter().any(|s| s.contains(symbol)) => Re::Epsilon,
+            Re::Set(_) => Re::Empty,
+            Re::Concat(items) => {
+                let rest = Re::concat(items[1..].to_vec());
+                let first = Re::concat(vec![items[0].derive(symbol), rest.clone()]);
+                if items[0].nullable() {
+                    Re::alt(vec![first, rest.derive(symbol)])
+                } else {
+                    first
+                }
+            }
+            Re::Alt(items) => Re::alt(items.iter().map(|r| r.derive(symbol)).collect()),
+            Re::And(items) => Re::and(items.iter().map(|r| r.derive(symbol)).collect()),
+            Re::Star(inner) => Re::concat(vec![inner.derive(symbol), self.clone()]),
+            Re::Not(inner) => Re::complement(inner.derive(symbol)),
+        }
+    }
+
+    fn sets<'a>(&'a self, sets: &mut Vec<&'a [Edge]>) {
+        match self {
+            Re::Empty | Re::Epsilon => {}
+            Re::Set(symbols) => sets.push(symbols),
+            Re::Concat(items) | Re::Alt(items) | Re::And(items) => {
+                items.iter().for_each(|r| r.sets(sets))
+            }
+            Re::Star(inner) | Re::Not(inner) => inner.sets(sets),
+        }
+    }
+}
+
+impl fmt::Display for Re {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |items: &[Re], sep: &str| {
+            items
+                .iter()
+                .map(|r| r.to_string())
+                .collect::<Vec<String>>()
+                .join(sep)
+        };
+        match self {
+            Re::Empty => write!(f, "∅"),
+            Re::Epsilon => write!(f, "ε"),
+            Re::Set(symbols) if symbols.len() == 1 => write!(f, "{}", symbols[0]),
+            // inside the brackets a range is just first-last
+            Re::Set(symbols) => write!(f, "[{}]", join_symbols(symbols)),
+            Re::Concat(items) => write!(f, "{}", join(items, "")),
+            Re::Alt(items) => write!(f, "({})", join(items, "|")),
+            Re::And(items) => write!(f, "({})", join(items, "&")),
+            Re::Star(inner) => write!(f, "{}*", inner),
+            Re::Not(inner) => write!(f, "!({})", inner),
+        }
+    }
+}
+
+fn join_symbols(symbols: &[Edge]) -> String {
+    symbols
+        .iter()
+        .map(|s| match s {
+            Edge::Range(..) | Edge::ByteRange(..) => {
+                let text = s.to_string();
+                text[1..text.len() - 1].to_string()
+            }
+            s => s.to_string(),
+        })
+        .collect::<String>()
+}
+
+// parses a pattern the same way nfa() does, so both routes accept the same patterns
+pub fn re(pattern: &str, options: Options) -> Result<Re, String> {
+    if let Some(comb) = crate::is_valid_regex(pattern) {
+        return Err(format!("Invalid regex string - contains: {}", comb));
+    }
+    let regex = parse(pattern, options).map_err(|e| format!("Invalid regex string - {}", e))?;
+    from_hir(&regex, options).map_err(|e| format!("Invalid regex string - {}", e))
+}
+
+fn from_hir(regex: &Hir, options: Options) -> Result<Re, &'static str> {
+    match regex.kind() {
+        hir::HirKind::Literal(hir::Literal::Unicode(c)) if options.bytes => {
+            let mut buf = [0; 4];
+            let bytes = c.encode_utf8(&mut buf).as_bytes();
+            Ok(Re::concat(
+                bytes
+                    .iter()
+                    .map(|b| Re::set(vec![Edge::Byte(*b)]))
+                    .collect(),
+            ))
+        }
+        hir::HirKind::Literal(hir::Literal::Unicode(c)) => Ok(Re::set(vec![Edge::Literal(*c)])),
+        hir::HirKind::Literal(hir::Literal::Byte(b)) if options.bytes => {
+            Ok(Re::set(vec![Edge::Byte(*b)]))
+        }
+        hir::HirKind::Literal(hir::Literal::Byte(_)) => Err("Invalid literal format"),
+        hir::HirKind::Class(class) if options.bytes => Ok(Re::alt(
+            class_byte_ranges(class)
+                .into_iter()
+                .map(|ranges| {
+                    Re::concat(
+                        ranges
+                            .into_iter()
+                            .filter_map(|(lo, hi)| Edge::from_bounds(lo as u32, hi as u32, true))
+                            .map(|range| Re::set(vec![range]))
+                            .collect(),
+                    )
+                })
+                .collect(),
+        )),
+        hir::HirKind::Class(class) => Ok(Re::set(class_ranges(class)?)),
+        hir::HirKind::Alternation(alts) => Ok(Re::alt(
+            alts.iter()
+                .map(|r| from_hir(r, options))
+                .collect::<Result<Vec<Re>, _>>()?,
+        )),
+        hir::HirKind::Concat(items) => Ok(Re::concat(
+            items
+                .iter()
+                .map(|r| from_hir(r, options))
+                .collect::<Result<Vec<Re>, _>>()?,
+        )),
+        hir::HirKind::Repetition(rep) => {
+            let inner = from_hir(&rep.hir, options)?;
+            match rep.kind {
+                hir::RepetitionKind::ZeroOrOne => Ok(Re::alt(vec![Re::Epsilon, inner])),
+                hir::RepetitionKind::ZeroOrMore => Ok(Re::star(inner)),
+                hir::RepetitionKind::OneOrMore => {
+                    Ok(Re::concat(vec![inner.clone(), Re::star(inner)]))
+                }
+                hir::RepetitionKind::Range(_) => Err("Range repetition is not allowed"),
+            }
+        }
+        hir::HirKind::Group(group) => match group.kind {
+            hir::GroupKind::CaptureName { .. } => Err("CaptureName groups not allowed"),
+            _ => from_hir(&group.hir, options),
+        },
+        hir::HirKind::Empty => Err("Epislon not allowed"),
+        hir::HirKind::Anchor(_) | hir::HirKind::WordBoundary(_) => {
+            Err("Assertions are not supported by the derivative construction")
+        }
+    }
+}
+
+// symbols between two places where some range of a set starts or ends are in exactly the same
+// sets, so they always have the same derivative
+fn classes(re: &Re) -> Vec<Edge> {
+    let mut sets = Vec::new();
+    re.sets(&mut sets);
+    let symbols = sets.iter().flat_map(|s| s.iter()).collect::<Vec<&Edge>>();
+    let bytes = symbols.iter().any(|s| s.is_byte());
+    let ranges = symbols
+        .iter()
+        .filter_map(|s| s.bounds())
+        .collect::<Vec<(u32, u32)>>();
+    pieces(&ranges, bytes, false)
+}
+
+// states are numbered in the order they are found, starting with re itself at node 0,
+// derivatives that match nothing get no edge so the dfa has no dead state (except for
+// node 0 when re matches nothing at all)
+pub fn dfa(re: &Re) -> DiGraph<bool, Edge> {
+    let classes = classes(re);
+    let mut dfa = DiGraph::<bool, Edge>::new();
+    let mut states = HashMap::<Re, _>::new();
+    let mut queue = VecDeque::new();
+    states.insert(re.clone(), dfa.add_node(re.nullable()));
+    queue.push_back(re.clone());
+
+    while let Some(current) = queue.pop_front() {
+        let from = states[&current];
+        for class in &classes {
+            let next = current.derive(&class.first());
+            if next == Re::Empty {
+                continue;
+            }
+            let to = match states.get(&next) {
+                Some(to) => *to,
+                None => {
+                    let to = dfa.add_node(next.nullable());
+                    states.insert(next.clone(), to);
+                    queue.push_back(next);
+                    to
+                }
+            };
+            dfa.add_edge(from, to, *class);
+        }
+    }
+
+    dfa
+}
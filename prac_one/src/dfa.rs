@@ -1,29 +1,129 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
 
-use crate::nfa::Edge;
+use crate::alphabet::{has_looks, Alphabet};
+use crate::nfa::{Edge, Kind, Weight};
 use petgraph::{adj::NodeIndex, prelude::DiGraph, visit::EdgeRef};
-pub fn dfa(graph: &DiGraph<bool, Edge>, start: NodeIndex) -> DiGraph<bool, Edge> {
-    let accepts = find_accepts(graph); // Get the accept states of the nfa
-    let literals = find_literals(graph); // get the alphabet over the nfa
-
-    let mut dfa = DiGraph::<bool, Edge>::new(); // init the new dfa
-    let mut states = HashMap::<Vec<u32>, (_, bool)>::new(); // init the states Hashmap
-                                                            // the key is a unique vector of nfa states that represent a dfa state
-                                                            // the value is a tuple storing:
-                                                            //  - NodeIndex of a dfa state
-                                                            //  - bool value representing if the accept states are in the vector of nfa states
-    let mut visited = Vec::<Vec<u32>>::new(); //what states have been visited
+
+// one processed dfa state of the subset construction, for the construction report
+#[derive(Debug, Clone)]
+pub struct SubsetStep {
+    pub dfa_state: u32,
+    pub nfa_states: Vec<u32>,
+    pub accept: bool,
+    pub moves: Vec<SubsetMove>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SubsetMove {
+    pub class: Edge,         // the class of symbols that all move the same way
+    pub moved: Vec<u32>,     // nfa states reached by the symbols
+    pub closure: Vec<u32>,   // epsilon closure of moved
+    pub target: Option<u32>, // None when nothing was reached
+}
+
+// how far the subset construction may go before giving up, None is no limit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    pub max_states: Option<usize>,
+    pub time_budget: Option<Duration>,
+}
+
+// the subset construction can need exponentially many dfa states, this is which limit it hit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlowUp {
+    States {
+        limit: usize,
+        nfa_states: usize,
+        processed: usize, // dfa states that had all their moves worked out
+    },
+    Time {
+        budget: Duration,
+        dfa_states: usize,
+        processed: usize,
+    },
+}
+
+impl fmt::Display for BlowUp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlowUp::States {
+                limit,
+                nfa_states,
+                processed,
+            } => write!(
+                f,
+                "DFA state blow-up: the {} NFA states needed more than {} DFA states ({} processed so far)",
+                nfa_states, limit, processed
+            ),
+            BlowUp::Time {
+                budget,
+                dfa_states,
+                processed,
+            } => write!(
+                f,
+                "DFA construction ran out of its {} ms budget with {} DFA states ({} processed so far)",
+                budget.as_millis(),
+                dfa_states,
+                processed
+            ),
+        }
+    }
+}
+
+pub fn dfa<N: Weight>(graph: &DiGraph<N, Edge>, start: NodeIndex) -> DiGraph<N, Edge> {
+    subset_construction(graph, start, None, Limits::default()).unwrap()
+}
+
+// same as dfa, failing instead of going past the limits
+pub fn dfa_limited<N: Weight>(
+    graph: &DiGraph<N, Edge>,
+    start: NodeIndex,
+    limits: Limits,
+) -> Result<DiGraph<N, Edge>, BlowUp> {
+    subset_construction(graph, start, None, limits)
+}
+
+// same as dfa_limited, also recording every step of the subset construction
+pub fn dfa_traced<N: Weight>(
+    graph: &DiGraph<N, Edge>,
+    start: NodeIndex,
+    limits: Limits,
+) -> Result<(DiGraph<N, Edge>, Vec<SubsetStep>), BlowUp> {
+    let mut steps = Vec::new();
+    let dfa = subset_construction(graph, start, Some(&mut steps), limits)?;
+    Ok((dfa, steps))
+}
+
+fn subset_construction<N: Weight>(
+    graph: &DiGraph<N, Edge>,
+    start: NodeIndex,
+    mut trace: Option<&mut Vec<SubsetStep>>,
+    limits: Limits,
+) -> Result<DiGraph<N, Edge>, BlowUp> {
+    let started = Instant::now();
+    let alphabet = Alphabet::new(graph); // get the alphabet over the nfa, as classes of symbols
+    let looks = has_looks(graph); // the previous symbol only matters when there are assertions
+
+    let mut dfa = DiGraph::<N, Edge>::new(); // init the new dfa
+    let mut states = HashMap::<State, (_, N)>::new(); // init the states Hashmap
+                                                      // the key is a unique vector of nfa states that represent a dfa state,
+                                                      // paired with the kind of symbol that was read to get there (None at the start)
+                                                      // the value is a tuple storing:
+                                                      //  - NodeIndex of a dfa state
+                                                      //  - the weights of its nfa states merged, for plain automata if any of them accepts
+    let mut visited = Vec::<State>::new(); //what states have been visited
 
     let closure = &mut Vec::<u32>::new(); // for consumption by eps close function
-    let start = epsilon_closure(closure, graph, start); // start state of dfa
-    let start_accept = check_accept(&accepts, start.to_vec()); // is start accept?
+    let mut start = epsilon_closure(closure, graph, start).to_owned(); // start state of dfa
+    start.sort();
+    let start = (start, None);
+    let start_accept = weight(graph, resolve_looks(&start, None, graph)); // is start accept?
     let start_index = dfa.add_node(start_accept); // new node for start
-    states.insert(start.to_vec(), (start_index, start_accept)); // add to Hashmap of states
+    states.insert(start, (start_index, start_accept)); // add to Hashmap of states
 
-    let mut keys = states
-        .keys()
-        .map(|k| k.to_owned())
-        .collect::<Vec<Vec<u32>>>();
+    let mut keys = states.keys().map(|k| k.to_owned()).collect::<Vec<State>>();
     //keys of states hashmap, i.e., list of vectors of nfa states representing the dfa states
     while !keys.eq(&visited) {
         // while keys and visisted do not equal
@@ -36,36 +136,99 @@ pub fn dfa(graph: &DiGraph<bool, Edge>, start: NodeIndex) -> DiGraph<bool, Edge>
                     None
                 }
             })
-            .collect::<Vec<Vec<u32>>>();
+            .collect::<Vec<State>>();
 
         if !not_visited.is_empty() {
+            if let Some(budget) = limits.time_budget {
+                if started.elapsed() > budget {
+                    return Err(BlowUp::Time {
+                        budget,
+                        dfa_states: dfa.node_count(),
+                        processed: visited.len(),
+                    });
+                }
+            }
             visited.push(not_visited[0].clone());
-            for literal in &literals {
-                let res = move_literal(&literal, not_visited[0].clone(), graph);
-                let state = states.get(&not_visited[0]).unwrap().0;
-                if let Some(new_state) = res {
+            let (state, accept) = *states.get(&not_visited[0]).unwrap();
+            let mut step = SubsetStep {
+                dfa_state: state.index() as u32,
+                nfa_states: not_visited[0].0.clone(),
+                accept: accept.accepts(),
+                moves: Vec::new(),
+            };
+            for class in 0..alphabet.len() {
+                // every symbol in a class moves to the same nfa states
+                let literal = alphabet.representative(class);
+                let next = Some(literal.kind());
+                let resolved = resolve_looks(&not_visited[0], next, graph);
+                if trace.is_some() {
+                    let mut moved = source_states(resolved.clone(), &literal, graph);
+                    moved.sort();
+                    moved.dedup();
+                    step.moves.push(SubsetMove {
+                        class: alphabet.classes[class],
+                        moved,
+                        closure: Vec::new(),
+                        target: None,
+                    });
+                }
+                let res = move_literal(&literal, resolved, graph);
+                if let Some(mut new_state) = res {
+                    new_state.sort();
+                    let new_state = (new_state, if looks { next } else { None });
+                    match limits.max_states {
+                        Some(limit)
+                            if dfa.node_count() >= limit && !states.contains_key(&new_state) =>
+                        {
+                            return Err(BlowUp::States {
+                                limit,
+                                nfa_states: graph.node_count(),
+                                processed: visited.len() - 1,
+                            })
+                        }
+                        _ => {}
+                    }
                     let entry = states.entry(new_state.clone()).or_insert_with(|| {
-                        let accept = check_accept(&accepts, new_state.to_vec());
+                        let accept = weight(graph, resolve_looks(&new_state, None, graph));
                         let index = dfa.add_node(accept);
                         (index, accept)
                     });
-                    dfa.add_edge(state.to_owned(), entry.0, Edge::Literal(*literal));
+                    dfa.add_edge(state.to_owned(), entry.0, alphabet.classes[class]);
+                    if let Some(last) = step.moves.last_mut() {
+                        last.closure = new_state.0.clone();
+                        last.target = Some(entry.0.index() as u32);
+                    }
                 }
             }
+            if let Some(trace) = trace.as_mut() {
+                trace.push(step);
+            }
         }
 
-        keys = states
-            .keys()
-            .map(|k| k.to_owned())
-            .collect::<Vec<Vec<u32>>>(); // update keys
+        keys = states.keys().map(|k| k.to_owned()).collect::<Vec<State>>(); // update keys
         visited.sort(); // sort visisted
         keys.sort(); // sort keys
     }
 
-    dfa
+    Ok(dfa)
+}
+
+// set of nfa states + kind of the symbol before it, which the assertions look behind at
+type State = (Vec<u32>, Option<Kind>);
+
+fn resolve_looks<N: Weight>(
+    state: &State,
+    next: Option<Kind>,
+    graph: &DiGraph<N, Edge>,
+) -> Vec<u32> {
+    look_closure(state.0.clone(), graph, state.1, next)
 }
 
-fn move_literal(literal: &char, state: Vec<u32>, graph: &DiGraph<bool, Edge>) -> Option<Vec<u32>> {
+pub fn move_literal<N: Weight>(
+    literal: &Edge,
+    state: Vec<u32>,
+    graph: &DiGraph<N, Edge>,
+) -> Option<Vec<u32>> {
     let source_states = source_states(state, literal, graph);
     let mut state = &mut Vec::<NodeIndex>::new();
 
@@ -80,17 +243,16 @@ fn move_literal(literal: &char, state: Vec<u32>, graph: &DiGraph<bool, Edge>) ->
     }
 }
 
-fn epsilon_closure<'a>(
+pub fn epsilon_closure<'a, N: Weight>(
     closure: &'a mut Vec<NodeIndex>,
-    graph: &'a DiGraph<bool, Edge>,
+    graph: &'a DiGraph<N, Edge>,
     start: NodeIndex,
 ) -> &'a mut Vec<NodeIndex> {
-    let mut edges = graph.edges(start.into());
     if !closure.contains(&start) {
         closure.push(start)
     }
-    while let Some(edge) = edges.next() {
-        if edge.weight() == &Edge::Epsilon && !closure.contains(&(edge.target().index() as u32)) {
+    for edge in graph.edges(start.into()) {
+        if edge.weight().is_epsilon() && !closure.contains(&(edge.target().index() as u32)) {
             closure.push(edge.target().index() as u32);
             epsilon_closure(closure, graph, edge.target().index() as u32);
         }
@@ -98,13 +260,38 @@ fn epsilon_closure<'a>(
     closure
 }
 
-fn source_states(state: Vec<u32>, literal: &char, graph: &DiGraph<bool, Edge>) -> Vec<u32> {
+// epsilon closure that also follows the assertion edges that hold between prev and next
+pub fn look_closure<N: Weight>(
+    state: Vec<u32>,
+    graph: &DiGraph<N, Edge>,
+    prev: Option<Kind>,
+    next: Option<Kind>,
+) -> Vec<u32> {
+    let mut closure = state.clone();
+    let mut stack = state;
+    while let Some(s) = stack.pop() {
+        for edge in graph.edges(s.into()) {
+            let follow = match edge.weight() {
+                Edge::Epsilon | Edge::Tag(_) => true,
+                Edge::Look(look) => look.holds(prev, next),
+                _ => false,
+            };
+            let target = edge.target().index() as u32;
+            if follow && !closure.contains(&target) {
+                closure.push(target);
+                stack.push(target);
+            }
+        }
+    }
+    closure.sort();
+    closure
+}
+
+fn source_states<N: Weight>(state: Vec<u32>, literal: &Edge, graph: &DiGraph<N, Edge>) -> Vec<u32> {
     graph
         .edge_references()
         .filter_map(|e| {
-            if e.weight() == &Edge::Literal(literal.clone())
-                && state.contains(&(e.source().index() as u32))
-            {
+            if e.weight().contains(literal) && state.contains(&(e.source().index() as u32)) {
                 Some(e.target().index() as u32)
             } else {
                 None
@@ -113,29 +300,24 @@ fn source_states(state: Vec<u32>, literal: &char, graph: &DiGraph<bool, Edge>) -
         .collect::<Vec<u32>>()
 }
 
-fn find_accepts(graph: &DiGraph<bool, Edge>) -> Vec<u32> {
+pub fn find_accepts<N: Weight>(graph: &DiGraph<N, Edge>) -> Vec<u32> {
     graph
         .node_indices()
-        .filter_map(|n| match graph.node_weight(n).unwrap() {
-            true => Some(n.index() as u32),
-            false => None,
-        })
+        .filter(|n| graph.node_weight(*n).unwrap().accepts())
+        .map(|n| n.index() as u32)
         .collect::<Vec<u32>>()
 }
 
-fn find_literals(graph: &DiGraph<bool, Edge>) -> HashSet<char> {
-    graph
-        .edge_references()
-        .filter_map(|e| match e.weight() {
-            Edge::Epsilon => None,
-            Edge::Literal(c) => Some(c.to_owned()),
-        })
-        .collect::<HashSet<char>>()
+fn weight<N: Weight>(graph: &DiGraph<N, Edge>, state: Vec<u32>) -> N {
+    state
+        .iter()
+        .map(|s| *graph.node_weight((*s).into()).unwrap())
+        .fold(N::default(), N::merge)
 }
 
-fn check_accept(accept: &Vec<u32>, state: Vec<u32>) -> bool {
+pub fn check_accept(accept: &[u32], state: Vec<u32>) -> bool {
     for s in &state {
-        if accept.contains(&s) {
+        if accept.contains(s) {
             return true;
         }
     }
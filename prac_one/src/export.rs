@@ -0,0 +1,148 @@
+use petgraph::{prelude::DiGraph, visit::EdgeRef};
+
+use crate::nfa::Edge;
+
+// all exporters take any stage of the pipeline, the start state is always node 0
+
+// root is the outer tag, <mindfa> for the min dfa like in out.xml
+pub fn to_xml(min_dfa: &DiGraph<bool, Edge>, root: &str) -> String {
+    let mut tab_count = 1;
+    let mut states = String::new();
+    tab_count += 1;
+    for n in min_dfa.node_indices() {
+        let mut tabs = String::new();
+        for _ in 0..tab_count {
+            tabs.push('\t');
+        }
+        let state = format!(
+            "{}<{}>{}</{}>\n",
+            tabs,
+            n.index() as u32,
+            min_dfa.node_weight(n).unwrap(),
+            n.index() as u32
+        );
+        states.push_str(&state);
+    }
+    states = states[..states.len() - 1].to_string();
+
+    let mut transitions = String::new();
+    for n in min_dfa.node_indices() {
+        let mut trans = String::new();
+        for edge in min_dfa.edges(n) {
+            let target = edge.target();
+            let weight = edge.weight();
+            tab_count += 1;
+            let mut tabs = String::new();
+            for _ in 0..tab_count {
+                tabs.push('\t');
+            }
+            let part = format!(
+                "{0}<{1}>{2}</{1}>\n",
+                tabs,
+                target.index(),
+                escape_xml(&weight.to_string())
+            );
+            trans.push_str(&part);
+            tab_count -= 1;
+        }
+        let mut tabs_one = String::new();
+        for _ in 0..tab_count {
+            tabs_one.push('\t');
+        }
+
+        let mut tabs_two = tabs_one.clone();
+        tabs_two.push('\t');
+        let part = format!("{0}<{1}>\n{2}{0}</{1}>\n", tabs_one, n.index(), trans);
+        transitions.push_str(&part);
+    }
+    transitions = transitions[..transitions.len() - 1].to_string();
+    format!(
+        r#"<{0}>
+    <states>
+{1}
+    </states>
+    <transitions>
+{2}
+    </transitions>
+</{0}>"#,
+        root, states, transitions
+    )
+}
+
+pub fn to_json(graph: &DiGraph<bool, Edge>) -> String {
+    let states = graph
+        .node_indices()
+        .map(|n| {
+            format!(
+                "    {{ \"id\": {}, \"accept\": {} }}",
+                n.index(),
+                graph.node_weight(n).unwrap()
+            )
+        })
+        .collect::<Vec<String>>();
+    let transitions = graph
+        .edge_references()
+        .map(|e| {
+            let kind = match e.weight() {
+                Edge::Epsilon => "epsilon",
+                Edge::Literal(_) => "literal",
+                Edge::Byte(_) => "byte",
+                Edge::Range(..) => "range",
+                Edge::ByteRange(..) => "byte_range",
+                Edge::Look(_) => "look",
+                Edge::Tag(_) => "tag",
+            };
+            format!(
+                "    {{ \"from\": {}, \"to\": {}, \"kind\": \"{}\", \"symbol\": \"{}\" }}",
+                e.source().index(),
+                e.target().index(),
+                kind,
+                escape_json(&e.weight().to_string())
+            )
+        })
+        .collect::<Vec<String>>();
+    format!(
+        "{{\n  \"start\": 0,\n  \"states\": [\n{}\n  ],\n  \"transitions\": [\n{}\n  ]\n}}\n",
+        states.join(",\n"),
+        transitions.join(",\n")
+    )
+}
+
+pub fn to_dot(graph: &DiGraph<bool, Edge>) -> String {
+    let mut dot = String::from("digraph {\n    rankdir = LR\n");
+    for n in graph.node_indices() {
+        let shape = match graph.node_weight(n).unwrap() {
+            true => "doublecircle",
+            false => "circle",
+        };
+        dot.push_str(&format!("    {} [ shape = {} ]\n", n.index(), shape));
+    }
+    for e in graph.edge_references() {
+        dot.push_str(&format!(
+            "    {} -> {} [ label = \"{}\" ]\n",
+            e.source().index(),
+            e.target().index(),
+            escape_json(&e.weight().to_string())
+        ));
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+pub fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+// also good enough for dot labels, which use the same quoting
+fn escape_json(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '"' => String::from("\\\""),
+            '\\' => String::from("\\\\"),
+            c if c.is_control() => format!("\\u{:04x}", c as u32),
+            c => c.to_string(),
+        })
+        .collect::<String>()
+}
@@ -0,0 +1,165 @@
+use petgraph::{graph::NodeIndex, prelude::DiGraph};
+
+use crate::nfa::{Edge, Look};
+
+// reads back what export::to_xml wrote. symbols are written with Display, so bytes and chars
+// look the same and bytes says which one they are, a [first-last] label is a range of them. only an <nfa> root can have epsilon, tag
+// and assertion edges, in a dfa or min dfa "ε" or "^" is just that char
+pub fn from_xml(xml: &str, bytes: bool) -> Result<DiGraph<bool, Edge>, String> {
+    let root = parse(xml)?;
+    let nfa = root.name == "nfa";
+    let child = |name: &str| {
+        root.children
+            .iter()
+            .find(|c| c.name == name)
+            .ok_or_else(|| format!("Missing <{}> in <{}>", name, root.name))
+    };
+
+    let mut graph = DiGraph::<bool, Edge>::new();
+    for (i, state) in child("states")?.children.iter().enumerate() {
+        if state.name != i.to_string() {
+            return Err(format!("State <{}> is out of order", state.name));
+        }
+        let accept = match state.text.as_str() {
+            "true" => true,
+            "false" => false,
+            other => return Err(format!("State {} is neither true nor false: {}", i, other)),
+        };
+        graph.add_node(accept);
+    }
+
+    let state = |name: &str| match name.parse::<usize>() {
+        Ok(n) if n < graph.node_count() => Ok(NodeIndex::new(n)),
+        _ => Err(format!("Unknown state <{}>", name)),
+    };
+    let mut edges = Vec::new();
+    for from in &child("transitions")?.children {
+        // to_xml lists the edges newest first, so they go back in the other way around
+        for to in from.children.iter().rev() {
+            edges.push((
+                state(&from.name)?,
+                state(&to.name)?,
+                symbol(&to.text, bytes, nfa)?,
+            ));
+        }
+    }
+    for (from, to, symbol) in edges {
+        graph.add_edge(from, to, symbol);
+    }
+
+    Ok(graph)
+}
+
+fn symbol(text: &str, bytes: bool, nfa: bool) -> Result<Edge, String> {
+    let edge = match text {
+        "ε" if nfa => Edge::Epsilon,
+        "^" if nfa => Edge::Look(Look::StartText),
+        "$" if nfa => Edge::Look(Look::EndText),
+        "(?m:^)" if nfa => Edge::Look(Look::StartLine),
+        "(?m:$)" if nfa => Edge::Look(Look::EndLine),
+        "\\b" if nfa => Edge::Look(Look::WordBoundary),
+        "\\B" if nfa => Edge::Look(Look::NotWordBoundary),
+        _ if nfa && text.starts_with('τ') && text.len() > 'τ'.len_utf8() => text
+            ['τ'.len_utf8()..]
+            .parse()
+            .map(Edge::Tag)
+            .map_err(|_| format!("Bad tag: {}", text))?,
+        _ if bytes && text.starts_with("\\x") && text.len() == 4 => {
+            u8::from_str_radix(&text[2..], 16)
+                .map(Edge::Byte)
+                .map_err(|_| format!("Bad byte: {}", text))?
+        }
+        _ if text.starts_with('[') && text.ends_with(']') && text.chars().count() > 1 => {
+            range(&text[1..text.len() - 1], bytes).ok_or_else(|| format!("Bad range: {}", text))?
+        }
+        _ => {
+            let mut chars = text.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if bytes && c.is_ascii() => Edge::Byte(c as u8),
+                (Some(c), None) if !bytes => Edge::Literal(c),
+                _ => return Err(format!("Bad symbol: {}", text)),
+            }
+        }
+    };
+    Ok(edge)
+}
+
+// the inside of a [first-last] class label, either end is written the same way as a symbol
+fn range(text: &str, bytes: bool) -> Option<Edge> {
+    let first = match text.chars().next()? {
+        '\\' if bytes => text.get(..4)?,
+        c => &text[..c.len_utf8()],
+    };
+    let last = text[first.len()..].strip_prefix('-')?;
+    let (lo, hi) = (
+        symbol(first, bytes, false).ok()?.bounds()?,
+        symbol(last, bytes, false).ok()?.bounds()?,
+    );
+    // a single symbol is never written as a range
+    if lo.0 < hi.0 {
+        Edge::from_bounds(lo.0, hi.0, bytes)
+    } else {
+        None
+    }
+}
+
+// just enough xml for what to_xml writes: nested elements with either children or text
+struct Element {
+    name: String,
+    text: String,
+    children: Vec<Element>,
+}
+
+fn parse(xml: &str) -> Result<Element, String> {
+    let mut stack = vec![Element {
+        name: String::new(),
+        text: String::new(),
+        children: Vec::new(),
+    }];
+    let mut rest = xml;
+    while let Some(open) = rest.find('<') {
+        stack
+            .last_mut()
+            .unwrap()
+            .text
+            .push_str(&unescape(&rest[..open]));
+        let close = rest[open..]
+            .find('>')
+            .ok_or_else(|| String::from("Unclosed tag"))?;
+        let tag = &rest[open + 1..open + close];
+        rest = &rest[open + close + 1..];
+
+        if let Some(name) = tag.strip_prefix('/') {
+            let element = stack.pop().unwrap();
+            if element.name != name || stack.is_empty() {
+                return Err(format!("Unexpected </{}>", name));
+            }
+            // the text around child elements is only indentation, a symbol can be whitespace though
+            let text = if element.children.is_empty() {
+                element.text
+            } else {
+                String::new()
+            };
+            let parent = stack.last_mut().unwrap();
+            parent.children.push(Element { text, ..element });
+        } else {
+            stack.push(Element {
+                name: tag.to_string(),
+                text: String::new(),
+                children: Vec::new(),
+            });
+        }
+    }
+
+    let mut document = stack.pop().unwrap();
+    if !stack.is_empty() || document.children.len() != 1 {
+        return Err(String::from("Expected exactly one root element"));
+    }
+    Ok(document.children.pop().unwrap())
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
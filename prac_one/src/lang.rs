@@ -0,0 +1,192 @@
+use std::collections::{hash_map::Entry, HashMap, VecDeque};
+
+use petgraph::{graph::NodeIndex, prelude::DiGraph, visit::EdgeRef, Direction::Incoming};
+
+use crate::alphabet::pieces;
+use crate::nfa::{Edge, Weight};
+
+// all of these expect a dfa with its start at node 0, e.g. the output of min_dfa
+
+// breadth first from the start, trying symbols in order, so the first accept state
+// reached gives the shortest word and the smallest one of that length (taking the first
+// symbol of every class)
+pub fn shortest(dfa: &DiGraph<bool, Edge>) -> Option<Vec<Edge>> {
+    let mut parent = vec![None; dfa.node_count()];
+    let mut seen = vec![false; dfa.node_count()];
+    let mut queue = VecDeque::from([0]);
+    seen[0] = true;
+
+    while let Some(state) = queue.pop_front() {
+        if *dfa.node_weight(NodeIndex::new(state)).unwrap() {
+            let mut word = Vec::new();
+            let mut at = state;
+            while let Some((from, symbol)) = parent[at] {
+                word.push(symbol);
+                at = from;
+            }
+            word.reverse();
+            return Some(word);
+        }
+        for (symbol, target) in sorted_edges(dfa, state) {
+            if !seen[target] {
+                seen[target] = true;
+                parent[target] = Some((state, symbol.first()));
+                queue.push_back(target);
+            }
+        }
+    }
+    None
+}
+
+// every accepted word of length <= max_len, shortest first and sorted within a length
+pub fn enumerate(dfa: &DiGraph<bool, Edge>, max_len: usize) -> Vec<Vec<Edge>> {
+    let live = live_states(dfa);
+    let mut words = Vec::new();
+    let mut level = if live[0] {
+        vec![(Vec::<Edge>::new(), 0)]
+    } else {
+        Vec::new()
+    };
+
+    for len in 0..=max_len {
+        for (word, state) in &level {
+            if *dfa.node_weight(NodeIndex::new(*state)).unwrap() {
+                words.push(word.clone());
+            }
+        }
+        if len == max_len {
+            break;
+        }
+        // extending a sorted level in symbol order keeps the next level sorted
+        let mut next = Vec::new();
+        for (word, state) in &level {
+            for (class, target) in sorted_edges(dfa, *state) {
+                if live[target] {
+                    for symbol in class.symbols() {
+                        let mut longer = word.clone();
+                        longer.push(symbol);
+                        next.push((longer, target));
+                    }
+                }
+            }
+        }
+        level = next;
+    }
+    words
+}
+
+// counts[n] is the number of accepted words of length n, saturating at u128::MAX
+pub fn count(dfa: &DiGraph<bool, Edge>, max_len: usize) -> Vec<u128> {
+    let mut paths = vec![0u128; dfa.node_count()];
+    paths[0] = 1;
+    let mut counts = Vec::new();
+
+    for len in 0..=max_len {
+        counts.push(
+            dfa.node_indices()
+                .filter(|n| *dfa.node_weight(*n).unwrap())
+                .fold(0u128, |sum, n| sum.saturating_add(paths[n.index()])),
+        );
+        if len == max_len {
+            break;
+        }
+        let mut next = vec![0u128; dfa.node_count()];
+        // every symbol of the class on the edge continues every path
+        for e in dfa.edge_references() {
+            let paths = paths[e.source().index()].saturating_mul(e.weight().size() as u128);
+            let target = &mut next[e.target().index()];
+            *target = target.saturating_add(paths);
+        }
+        paths = next;
+    }
+    counts
+}
+
+// shortest word accepted by exactly one of the two dfas, None when their languages are equal,
+// walks both dfas side by side with None standing for the missing dead state
+pub fn distinguish(a: &DiGraph<bool, Edge>, b: &DiGraph<bool, Edge>) -> Option<Vec<Edge>> {
+    let accepts = |dfa: &DiGraph<bool, Edge>, state: Option<usize>| {
+        state.is_some_and(|s| *dfa.node_weight(NodeIndex::new(s)).unwrap())
+    };
+    let step = |dfa: &DiGraph<bool, Edge>, state: Option<usize>, symbol: &Edge| {
+        state.and_then(|s| {
+            dfa.edges(NodeIndex::new(s))
+                .find(|e| e.weight().contains(symbol))
+                .map(|e| e.target().index())
+        })
+    };
+
+    let start = (Some(0), Some(0));
+    let mut parent = HashMap::<(Option<usize>, Option<usize>), Option<(_, Edge)>>::new();
+    parent.insert(start, None);
+    let mut queue = VecDeque::from([start]);
+    while let Some(pair) = queue.pop_front() {
+        if accepts(a, pair.0) != accepts(b, pair.1) {
+            let mut word = Vec::new();
+            let mut at = pair;
+            while let Some((from, symbol)) = parent[&at] {
+                word.push(symbol);
+                at = from;
+            }
+            word.reverse();
+            return Some(word);
+        }
+
+        // the classes of the two dfas needn't line up, so step with a symbol of every piece
+        // the edges of both states cut the symbols into
+        let edges = [(a, pair.0), (b, pair.1)]
+            .iter()
+            .filter_map(|(dfa, state)| state.map(|s| sorted_edges(dfa, s)))
+            .flatten()
+            .map(|(class, _)| class)
+            .collect::<Vec<Edge>>();
+        let ranges = edges
+            .iter()
+            .filter_map(|e| e.bounds())
+            .collect::<Vec<(u32, u32)>>();
+        let bytes = edges.iter().any(|e| e.is_byte());
+        for symbol in pieces(&ranges, bytes, false).iter().map(|p| p.first()) {
+            let next = (step(a, pair.0, &symbol), step(b, pair.1, &symbol));
+            if let Entry::Vacant(entry) = parent.entry(next) {
+                entry.insert(Some((pair, symbol)));
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+pub fn to_string(word: &[Edge]) -> String {
+    word.iter().map(|s| s.to_string()).collect::<String>()
+}
+
+// states that can still reach an accept state
+pub fn live_states<N: Weight>(dfa: &DiGraph<N, Edge>) -> Vec<bool> {
+    let mut live = dfa
+        .node_indices()
+        .map(|n| dfa.node_weight(n).unwrap().accepts())
+        .collect::<Vec<bool>>();
+    let mut stack = dfa
+        .node_indices()
+        .filter(|n| live[n.index()])
+        .collect::<Vec<_>>();
+    while let Some(state) = stack.pop() {
+        for e in dfa.edges_directed(state, Incoming) {
+            if !live[e.source().index()] {
+                live[e.source().index()] = true;
+                stack.push(e.source());
+            }
+        }
+    }
+    live
+}
+
+// the outgoing edges of a state, in symbol order
+pub fn sorted_edges<N>(dfa: &DiGraph<N, Edge>, state: usize) -> Vec<(Edge, usize)> {
+    let mut edges = dfa
+        .edges(NodeIndex::new(state))
+        .map(|e| (*e.weight(), e.target().index()))
+        .collect::<Vec<(Edge, usize)>>();
+    edges.sort_by_key(|(e, target)| (e.bounds(), *e, *target));
+    edges
+}
@@ -0,0 +1,105 @@
+// regex -> nfa -> dfa -> min dfa as a library, main.rs is just the command line on top of it
+// every automaton is a DiGraph with the start state at node 0 and the accept flag on the nodes
+mod alphabet;
+pub mod analyze;
+pub mod canon;
+pub mod combine;
+pub mod dense;
+pub mod deriv;
+pub mod dfa;
+pub mod export;
+pub mod import;
+pub mod lang;
+pub mod min_dfa;
+pub mod nfa;
+pub mod report;
+pub mod sim;
+pub mod simplify;
+pub mod tdfa;
+pub mod transducer;
+pub mod trim;
+
+use petgraph::prelude::DiGraph;
+
+pub use export::{to_dot, to_json, to_xml};
+pub use nfa::{Edge, Options};
+pub use trim::Trimmed;
+
+pub type Nfa = DiGraph<bool, Edge>;
+pub type Dfa = DiGraph<bool, Edge>;
+
+// every stage of the pipeline for one regex
+#[derive(Debug, Clone)]
+pub struct Compiled {
+    pub nfa: Nfa,
+    pub dfa: Dfa,
+    pub min_dfa: Dfa,
+    pub trimmed: Trimmed, // what got trimmed from the dfa before minimizing
+}
+
+// how big every stage came out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub nfa_nodes: usize,
+    pub nfa_edges: usize,
+    pub symbols: usize, // distinct symbols on the nfa edges
+    pub classes: usize, // classes of symbols no transition tells apart, see alphabet.rs
+    pub dfa_states: usize,
+    pub min_states: usize,
+}
+
+impl Compiled {
+    pub fn stats(&self) -> Stats {
+        Stats::new(&self.nfa, &self.dfa, &self.min_dfa)
+    }
+}
+
+impl Stats {
+    pub fn new(nfa: &Nfa, dfa: &Dfa, min_dfa: &Dfa) -> Self {
+        let alphabet = alphabet::Alphabet::new(nfa);
+        Stats {
+            nfa_nodes: nfa.node_count(),
+            nfa_edges: nfa.edge_count(),
+            symbols: alphabet.size() as usize,
+            classes: alphabet.len(),
+            dfa_states: dfa.node_count(),
+            min_states: min_dfa.node_count(),
+        }
+    }
+}
+
+pub fn compile(pattern: &str, options: Options) -> Result<Compiled, String> {
+    compile_limited(pattern, options, dfa::Limits::default())
+}
+
+// same as compile, giving up when the subset construction goes past the limits
+pub fn compile_limited(
+    pattern: &str,
+    options: Options,
+    limits: dfa::Limits,
+) -> Result<Compiled, String> {
+    let nfa = build_nfa(pattern, options)?;
+    let dfa = dfa::dfa_limited(&nfa, 0, limits).map_err(|e| e.to_string())?;
+    let (min_dfa, trimmed) = min_dfa::min_dfa_trimmed(dfa.clone());
+    Ok(Compiled {
+        nfa,
+        dfa,
+        min_dfa,
+        trimmed,
+    })
+}
+
+pub fn build_nfa(pattern: &str, options: Options) -> Result<Nfa, String> {
+    if let Some(comb) = is_valid_regex(pattern) {
+        return Err(format!("Invalid regex string - contains: {}", comb));
+    }
+    nfa::nfa(pattern.to_owned(), options).map_err(|e| format!("Invalid regex string - {}", e))
+}
+
+fn is_valid_regex(regex_str: &str) -> Option<&str> {
+    let invalid_combinations = &["**", "++", "?*", "+*", "*+", "|*", "|+", "|?"];
+    invalid_combinations
+        .iter()
+        .find(|comb| regex_str.contains(*comb))
+        .copied()
+}
@@ -1,108 +1,688 @@
-mod dfa;
-pub mod min_dfa;
-mod nfa;
-use std::{env};
+mod batch;
 
-use min_dfa::min_dfa;
-use nfa::Edge;
-use petgraph::visit::EdgeRef;
-use petgraph::{dot::Dot, prelude::DiGraph};
-
-use std::fs::File;
+use std::fs;
 use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
 
-fn main() {
-    let mut args = env::args();
-
-    if let Some(raw) = &args.nth(1) {
-        if let Some(comb) = is_valid_regex(raw) {
-            println!("Invalid regex string - contains: {}", comb);
-            return;
-        }
-        let input = raw.replace(" ", "");
-        let nfa = nfa::nfa(input);
-        // println!("{:?}", Dot::new(&nfa));
-        let dfa = dfa::dfa(&nfa, nfa.node_indices().next().unwrap().index() as u32);
-        // println!("{:?}", Dot::new(&dfa));
-        let min_dfa = min_dfa(dfa);
-        println!("{:?}", Dot::new(&min_dfa));
-        to_xml(min_dfa);
-    } else {
-        println!("No Input Provided...")
-    }
+use clap::error::ErrorKind;
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
+use prac_1::{
+    analyze, build_nfa, combine, dense, deriv, dfa, export, import, lang, min_dfa, nfa, report,
+    sim, simplify, tdfa, transducer, Compiled, Dfa, Edge, Stats,
+};
+
+#[derive(Parser)]
+#[command(name = "prac_one", about = "Regex to NFA, DFA and minimal DFA")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+
+    /// Build the automata over UTF-8 bytes instead of chars
+    #[arg(long, global = true)]
+    bytes: bool,
+
+    /// Same as starting the pattern with (?i)
+    #[arg(long, global = true)]
+    ignore_case: bool,
+
+    /// Print more, -v shows the automaton as DOT and what trimming removed
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Print the size of every stage (NFA nodes and edges, alphabet, DFA and MINDFA states) to stderr
+    #[arg(long, global = true)]
+    stats: bool,
+
+    /// Give up when the subset construction needs more DFA states than this
+    #[arg(long, global = true)]
+    max_states: Option<usize>,
+
+    /// Give up when the subset construction takes longer than this many milliseconds
+    #[arg(long, global = true)]
+    time_budget: Option<u64>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Compile a regex and write the automaton to a file (out.xml by default)
+    Compile {
+        pattern: String,
+        #[command(flatten)]
+        output: Output,
+        /// Also write the min DFA as a dense table to this file
+        #[arg(long)]
+        dense: Option<PathBuf>,
+    },
+    /// Check words against a regex (or a saved dense DFA), exits with 1 if any is rejected
+    Match {
+        /// Required unless --load is given
+        #[arg(required_unless_present = "load")]
+        pattern: Option<String>,
+        /// Words to check, read from stdin when there are none
+        words: Vec<String>,
+        /// Match with a dense DFA written by compile --dense, every positional is a word then
+        #[arg(long)]
+        load: Option<PathBuf>,
+        /// Print the active NFA states after every symbol
+        #[arg(long, conflicts_with_all = ["load", "search"])]
+        trace: bool,
+        /// Look for the leftmost longest match anywhere in the word
+        #[arg(long, conflicts_with = "load")]
+        search: bool,
+        /// Print where each capture group matched, with a tagged DFA
+        #[arg(long, conflicts_with_all = ["load", "search", "trace"])]
+        captures: bool,
+    },
+    /// Check if two regexes accept the same language, exits with 1 if they don't
+    Equiv { first: String, second: String },
+    /// Print a stage of the pipeline in some format (to stdout by default)
+    Export {
+        pattern: String,
+        #[command(flatten)]
+        output: Output,
+    },
+    /// Report emptiness, finiteness, universality and unreachable/dead states
+    Analyze { pattern: String },
+    /// Compile every regex in a file (one per line or a .toml of names) into a directory
+    Batch {
+        file: PathBuf,
+        #[arg(long, default_value = ".")]
+        out_dir: PathBuf,
+    },
+    /// Shortest word, counts per length and every accepted word up to a length
+    Words {
+        pattern: String,
+        #[arg(long, default_value_t = 4)]
+        max_len: usize,
+    },
+    /// Build the DFA from Brzozowski derivatives instead, this also does intersection and complement
+    Derive {
+        pattern: String,
+        /// Only accept words this regex accepts too, can be given more than once
+        #[arg(long)]
+        and: Vec<String>,
+        /// Accept every word over the symbols of the regexes that would be rejected instead
+        #[arg(long)]
+        not: bool,
+        /// The DFA straight from the derivatives instead of the minimal one
+        #[arg(long)]
+        dfa: bool,
+        #[arg(long, value_enum, default_value_t = Format::Xml)]
+        format: Format,
+        /// Where to write it (stdout by default)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Build an automaton out of xml files written by compile and write it like compile does
+    Combine {
+        #[arg(value_enum)]
+        operation: Operation,
+        /// reverse, prefix and suffix take one file, the rest take one or more
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        #[command(flatten)]
+        output: Output,
+    },
+    /// Step by step report of the subset construction and the partition refinement
+    Trace {
+        pattern: String,
+        #[arg(long, value_enum, default_value_t = ReportFormat::Md)]
+        format: ReportFormat,
+        /// Where to write it (stdout by default)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Print the regex the NFA is actually built from, after simplifying it
+    Simplify {
+        pattern: String,
+        /// Keep the capture groups and the order things are tried in, like match --captures does
+        #[arg(long)]
+        captures: bool,
+    },
+    /// Replace the longest match of any rule (`pattern => output` lines) in each word
+    Rewrite {
+        rules: PathBuf,
+        /// Words to rewrite, read from stdin when there are none
+        words: Vec<String>,
+        /// Run each whole word through as a Moore machine, writing the output of every state entered
+        #[arg(long)]
+        moore: bool,
+    },
+}
+
+#[derive(Args)]
+#[group(multiple = false)]
+struct StageArgs {
+    /// The Thompson NFA
+    #[arg(long)]
+    nfa: bool,
+    /// The DFA from the subset construction
+    #[arg(long)]
+    dfa: bool,
+    /// The minimal DFA (default)
+    #[arg(long)]
+    min: bool,
 }
 
-fn is_valid_regex(regex_str: &str) -> Option<&str> {
-    let invalid_combinations = &["**", "++", "?*", "*?", "+*", "*+", "|*", "|+", "|?"];
-    for comb in invalid_combinations {
-        if regex_str.contains(comb) {
-            return Some(comb);
+#[derive(Args)]
+struct Output {
+    #[command(flatten)]
+    stage: StageArgs,
+    #[arg(long, value_enum, default_value_t = Format::Xml)]
+    format: Format,
+    /// Where to write it
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Xml,
+    Json,
+    Dot,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Operation {
+    Reverse,
+    Prefix,
+    Suffix,
+    Concat,
+    Union,
+    Star,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ReportFormat {
+    Md,
+    Html,
+}
+
+// exit codes, clap uses 2 for usage errors as well
+const REJECTED: u8 = 1;
+const FAILED: u8 = 2;
+
+fn main() -> ExitCode {
+    // `prac_one "regex"` without a subcommand still compiles to out.xml like it used to, the
+    // global options are fine after the subcommand so it can go first
+    let mut args = std::env::args().collect::<Vec<String>>();
+    let cli = match Cli::try_parse_from(&args) {
+        Ok(cli) => cli,
+        Err(e) if e.kind() == ErrorKind::InvalidSubcommand => {
+            args.insert(1, String::from("compile"));
+            Cli::try_parse_from(args).unwrap_or_else(|_| e.exit())
+        }
+        Err(e) => e.exit(),
+    };
+    match run(&cli) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::from(FAILED)
         }
     }
-    None
-}
-
-fn to_xml(min_dfa: DiGraph<bool, Edge>) {
-    let mut file = File::create("out.xml").unwrap();
-    let mut tab_count = 1;
-    let mut states = String::new();
-    tab_count += 1;
-    for n in min_dfa.node_indices() {
-        let mut tabs = String::new();
-        for _ in 0..tab_count {
-            tabs.push('\t');
-        }
-        let state = format!(
-            "{}<{}>{}</{}>\n",
-            tabs,
-            n.index() as u32,
-            min_dfa.node_weight(n.clone()).unwrap(),
-            n.index() as u32
-        );
-        states.push_str(&state);
+}
+
+fn run(cli: &Cli) -> Result<ExitCode, String> {
+    let options = nfa::Options {
+        bytes: cli.bytes,
+        ignore_case: cli.ignore_case,
+        tags: false,
+    };
+
+    // these never run the subset construction, so there are no stages to count
+    if cli.stats
+        && matches!(
+            cli.command,
+            Command::Derive { .. } | Command::Simplify { .. } | Command::Rewrite { .. }
+        )
+    {
+        return Err(String::from(
+            "--stats only works with commands that compile a regex to a DFA",
+        ));
     }
-    states = states[..states.len() - 1].to_string();
-
-    let mut transitions = String::new();
-    for n in min_dfa.node_indices() {
-        let mut trans = String::new();
-        let mut edges = min_dfa.edges(n);
-        while let Some(edge) = edges.next() {
-            let target = edge.target();
-            let weight = match edge.weight().clone() {
-                Edge::Epsilon => 'ε',
-                Edge::Literal(c) => c,
+
+    match &cli.command {
+        Command::Compile {
+            pattern,
+            output,
+            dense,
+        } => {
+            let Compiled {
+                nfa, dfa, min_dfa, ..
+            } = compile(pattern, options, cli)?;
+            let graph = match output.stage {
+                StageArgs { nfa: true, .. } => &nfa,
+                StageArgs { dfa: true, .. } => &dfa,
+                _ => &min_dfa,
             };
-            tab_count += 1;
-            let mut tabs = String::new();
-            for _ in 0..tab_count {
-                tabs.push('\t');
+            if cli.verbose > 0 {
+                print!("{}", export::to_dot(graph));
+            }
+            if let Some(path) = dense {
+                // precompiled table, see dense.rs for the layout
+                write(path, dense::DenseDfa::new(&min_dfa).as_bytes())?;
             }
-            let part = format!("{0}<{1}>{2}</{1}>\n", tabs, target.index(), weight);
-            trans.push_str(&part);
-            tab_count -= 1;
+            let path = output
+                .output
+                .clone()
+                .unwrap_or_else(|| PathBuf::from("out.xml"));
+            write(&path, export_stage(graph, output).as_bytes())?;
+            Ok(ExitCode::SUCCESS)
         }
-        let mut tabs_one = String::new();
-        for _ in 0..tab_count {
-            tabs_one.push('\t');
+        Command::Export { pattern, output } => {
+            let Compiled {
+                nfa, dfa, min_dfa, ..
+            } = compile(pattern, options, cli)?;
+            let graph = match output.stage {
+                StageArgs { nfa: true, .. } => &nfa,
+                StageArgs { dfa: true, .. } => &dfa,
+                _ => &min_dfa,
+            };
+            match &output.output {
+                Some(path) => write(path, export_stage(graph, output).as_bytes())?,
+                None => print!("{}", export_stage(graph, output)),
+            }
+            Ok(ExitCode::SUCCESS)
         }
+        Command::Match {
+            pattern,
+            words,
+            load,
+            trace,
+            search,
+            captures,
+        } => {
+            // with --load there is no pattern, so the first positional is a word too
+            let words = match (load, pattern) {
+                (Some(_), Some(first)) => [vec![first.to_owned()], words.to_owned()].concat(),
+                _ => words.to_owned(),
+            };
+            let words = if words.is_empty() {
+                stdin_lines()?
+            } else {
+                words
+            };
+
+            if cli.stats && (load.is_some() || *captures) {
+                return Err(String::from(
+                    "--stats doesn't work with --load or --captures",
+                ));
+            }
 
-        let mut tabs_two = tabs_one.clone();
-        tabs_two.push('\t');
-        let part = format!("{0}<{1}>\n{2}{0}</{1}>\n", tabs_one, n.index(), trans);
-        transitions.push_str(&part);
+            let mut all = true;
+            if let Some(path) = load {
+                // match with a dfa saved by compile --dense
+                let bytes = fs::read(path).map_err(|e| format!("Unable to read file: {}", e))?;
+                let dense = dense::DenseDfa::from_bytes(&bytes)?;
+                for word in &words {
+                    let accepted = dense.is_match_bytes(word.as_bytes());
+                    print_verdict(word, accepted);
+                    all &= accepted;
+                }
+            } else if *captures {
+                let pattern = pattern.as_deref().unwrap_or_default();
+                let tags = nfa::Options {
+                    tags: true,
+                    ..options
+                };
+                let tdfa = tdfa::tagged_dfa_limited(&build_nfa(pattern, tags)?, limits(cli))?;
+                for word in &words {
+                    let symbols = symbols(word, options);
+                    let groups = tdfa.captures(&symbols);
+                    print_verdict(word, groups.is_some());
+                    print_captures(&symbols, groups.as_deref().unwrap_or_default());
+                    all &= groups.is_some();
+                }
+            } else {
+                let pattern = pattern.as_deref().unwrap_or_default();
+                let Compiled { nfa, min_dfa, .. } = compile(pattern, options, cli)?;
+                let dense = dense::DenseDfa::new(&min_dfa);
+                for word in &words {
+                    let symbols = symbols(word, options);
+                    let accepted = if *search {
+                        let found = sim::find(&nfa, 0, &symbols);
+                        match found {
+                            Some((from, to)) => println!(
+                                "match at {}..{}: \"{}\"",
+                                from,
+                                to,
+                                lang::to_string(&symbols[from..to])
+                            ),
+                            None => println!("no match in \"{}\"", word),
+                        }
+                        found.is_some()
+                    } else if *trace {
+                        // trace the word through the nfa instead of the min dfa
+                        let (accepted, trace) = if options.bytes {
+                            sim::simulate_bytes(&nfa, 0, word.as_bytes())
+                        } else {
+                            sim::simulate(&nfa, 0, word)
+                        };
+                        sim::print_trace(word, accepted, &trace);
+                        accepted
+                    } else {
+                        let accepted = dense.is_match(word);
+                        print_verdict(word, accepted);
+                        accepted
+                    };
+                    all &= accepted;
+                }
+            }
+            Ok(exit_code(all))
+        }
+        Command::Equiv { first, second } => {
+            let first = compile(first, options, cli)?.min_dfa;
+            let second = compile(second, options, cli)?.min_dfa;
+            match lang::distinguish(&first, &second) {
+                None => {
+                    println!("equivalent");
+                    Ok(ExitCode::SUCCESS)
+                }
+                Some(word) => {
+                    println!(
+                        "not equivalent, \"{}\" is only accepted by one of them",
+                        lang::to_string(&word)
+                    );
+                    Ok(ExitCode::from(REJECTED))
+                }
+            }
+        }
+        Command::Analyze { pattern } => {
+            // the dfa before trimming, the analysis reports what trimming would remove
+            let dfa = compile(pattern, options, cli)?.dfa;
+            print_analysis(&analyze::analyze(&dfa));
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Batch { file, out_dir } => {
+            if batch::batch(file, out_dir, options, limits(cli), cli.stats)? {
+                Ok(ExitCode::SUCCESS)
+            } else {
+                Ok(ExitCode::from(FAILED))
+            }
+        }
+        Command::Words { pattern, max_len } => {
+            let min_dfa = compile(pattern, options, cli)?.min_dfa;
+            print_words(&min_dfa, *max_len);
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Derive {
+            pattern,
+            and,
+            not,
+            dfa,
+            format,
+            output,
+        } => {
+            let mut re = deriv::re(pattern, options)?;
+            if !and.is_empty() {
+                let mut all = vec![re];
+                for other in and {
+                    all.push(deriv::re(other, options)?);
+                }
+                re = deriv::Re::and(all);
+            }
+            if *not {
+                re = deriv::Re::complement(re);
+            }
+            if cli.verbose > 0 {
+                eprintln!("{}", re);
+            }
+            let graph = if *dfa {
+                deriv::dfa(&re)
+            } else {
+                min_dfa::min_dfa(deriv::dfa(&re))
+            };
+            let root = if *dfa { "dfa" } else { "mindfa" };
+            let text = match format {
+                Format::Xml => export::to_xml(&graph, root),
+                Format::Json => export::to_json(&graph),
+                Format::Dot => export::to_dot(&graph),
+            };
+            match output {
+                Some(path) => write(path, text.as_bytes())?,
+                None => print!("{}", text),
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Combine {
+            operation,
+            files,
+            output,
+        } => {
+            let mut automata = Vec::new();
+            for path in files {
+                let xml = fs::read_to_string(path)
+                    .map_err(|e| format!("Unable to read {}: {}", path.display(), e))?;
+                let automaton = import::from_xml(&xml, options.bytes)
+                    .map_err(|e| format!("Unable to load {}: {}", path.display(), e))?;
+                automata.push(automaton);
+            }
+            let single = || match automata.as_slice() {
+                [automaton] => Ok(automaton),
+                _ => Err(String::from("This operation takes exactly one file")),
+            };
+            let nfa = match operation {
+                Operation::Reverse => combine::reverse(single()?),
+                Operation::Prefix => combine::prefix(single()?),
+                Operation::Suffix => combine::suffix(single()?),
+                Operation::Concat => combine::concat(&automata),
+                Operation::Union => combine::union(&automata),
+                Operation::Star => combine::star(&automata),
+            };
+            let dfa = dfa::dfa_limited(&nfa, 0, limits(cli)).map_err(|e| e.to_string())?;
+            let min_dfa = min_dfa::min_dfa(dfa.clone());
+            if cli.stats {
+                let files = files
+                    .iter()
+                    .map(|f| f.display().to_string())
+                    .collect::<Vec<String>>();
+                print_stats(&files.join(" "), &Stats::new(&nfa, &dfa, &min_dfa));
+            }
+            let graph = match output.stage {
+                StageArgs { nfa: true, .. } => &nfa,
+                StageArgs { dfa: true, .. } => &dfa,
+                _ => &min_dfa,
+            };
+            let path = output
+                .output
+                .clone()
+                .unwrap_or_else(|| PathBuf::from("out.xml"));
+            write(&path, export_stage(graph, output).as_bytes())?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Trace {
+            pattern,
+            format,
+            output,
+        } => {
+            let nfa = build_nfa(pattern, options)?;
+            let (dfa, steps) = dfa::dfa_traced(&nfa, 0, limits(cli)).map_err(|e| e.to_string())?;
+            let (min_dfa, history) = min_dfa::min_dfa_history(dfa.clone());
+            if cli.stats {
+                print_stats(pattern, &Stats::new(&nfa, &dfa, &min_dfa));
+            }
+            let tables = report::tables(&steps, &history, &min_dfa);
+            let title = format!("Subset construction and minimization of {}", pattern);
+            let report = match format {
+                ReportFormat::Md => report::to_markdown(&title, &tables),
+                ReportFormat::Html => report::to_html(&title, &tables),
+            };
+            match output {
+                Some(path) => write(path, report.as_bytes())?,
+                None => print!("{}", report),
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Simplify { pattern, captures } => {
+            let options = nfa::Options {
+                tags: *captures,
+                ..options
+            };
+            let regex = nfa::parse(pattern, options)
+                .map_err(|e| format!("Invalid regex string - {}", e))?;
+            println!("{}", simplify::simplify(&regex, options));
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Rewrite {
+            rules,
+            words,
+            moore,
+        } => {
+            let source = fs::read_to_string(rules)
+                .map_err(|e| format!("Unable to read {}: {}", rules.display(), e))?;
+            let transducer =
+                transducer::Transducer::new(&transducer::parse_rules(&source)?, options)?;
+            if cli.verbose > 0 {
+                eprintln!("{} min DFA states", transducer.dfa.node_count());
+            }
+            let words = if words.is_empty() {
+                stdin_lines()?
+            } else {
+                words.to_owned()
+            };
+            let mut all = true;
+            for word in &words {
+                if !*moore {
+                    println!("{}", transducer.rewrite(word));
+                    continue;
+                }
+                let output = transducer.translate(word);
+                match &output {
+                    Some(output) => println!("{}", output),
+                    None => println!("\"{}\" rejected", word),
+                }
+                all &= output.is_some();
+            }
+            Ok(exit_code(all))
+        }
+    }
+}
+
+fn stdin_lines() -> Result<Vec<String>, String> {
+    let mut input = String::new();
+    std::io::stdin()
+        .read_to_string(&mut input)
+        .map_err(|e| format!("Unable to read stdin: {}", e))?;
+    Ok(input.lines().map(String::from).collect())
+}
+
+fn compile(pattern: &str, options: nfa::Options, cli: &Cli) -> Result<Compiled, String> {
+    let compiled = prac_1::compile_limited(pattern, options, limits(cli))?;
+    let trimmed = &compiled.trimmed;
+    if cli.verbose > 0 && (!trimmed.unreachable.is_empty() || !trimmed.dead.is_empty()) {
+        eprintln!(
+            "trimmed unreachable states {:?} and dead states {:?}",
+            trimmed.unreachable, trimmed.dead
+        );
+    }
+    if cli.stats {
+        print_stats(pattern, &compiled.stats());
+    }
+    Ok(compiled)
+}
+
+fn limits(cli: &Cli) -> dfa::Limits {
+    dfa::Limits {
+        max_states: cli.max_states,
+        time_budget: cli.time_budget.map(Duration::from_millis),
     }
-    transitions = transitions[..transitions.len() - 1].to_string();
-    let xml = format!(
-        r#"<mindfa>
-    <states>
-{}
-    </states>
-    <transitions>
-{}
-    </transitions>
-</mindfa>"#,
-        states, transitions
+}
+
+fn print_stats(pattern: &str, stats: &Stats) {
+    eprintln!("stats for {}:", pattern);
+    eprintln!(
+        "  nfa: {} nodes, {} edges",
+        stats.nfa_nodes, stats.nfa_edges
     );
-    file.write_all(xml.as_bytes()).unwrap();
+    eprintln!(
+        "  alphabet: {} symbols in {} classes",
+        stats.symbols, stats.classes
+    );
+    eprintln!("  dfa: {} states", stats.dfa_states);
+    eprintln!("  min dfa: {} states", stats.min_states);
+}
+
+fn export_stage(graph: &Dfa, output: &Output) -> String {
+    match output.format {
+        Format::Xml => {
+            let root = match output.stage {
+                StageArgs { nfa: true, .. } => "nfa",
+                StageArgs { dfa: true, .. } => "dfa",
+                _ => "mindfa",
+            };
+            export::to_xml(graph, root)
+        }
+        Format::Json => export::to_json(graph),
+        Format::Dot => export::to_dot(graph),
+    }
+}
+
+fn write(path: &Path, contents: &[u8]) -> Result<(), String> {
+    fs::write(path, contents).map_err(|e| format!("Unable to write {}: {}", path.display(), e))
+}
+
+fn exit_code(success: bool) -> ExitCode {
+    if success {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(REJECTED)
+    }
+}
+
+fn print_verdict(word: &str, accepted: bool) {
+    if accepted {
+        println!("\"{}\" accepted", word);
+    } else {
+        println!("\"{}\" rejected", word);
+    }
+}
+
+fn print_captures(symbols: &[Edge], groups: &[Option<(usize, usize)>]) {
+    for (group, span) in groups.iter().enumerate() {
+        match span {
+            Some((from, to)) => println!(
+                "  group {}: {}..{} \"{}\"",
+                group,
+                from,
+                to,
+                lang::to_string(&symbols[*from..*to])
+            ),
+            None => println!("  group {}: no match", group),
+        }
+    }
+}
+
+fn symbols(word: &str, options: nfa::Options) -> Vec<Edge> {
+    if options.bytes {
+        word.bytes().map(Edge::Byte).collect()
+    } else {
+        word.chars().map(Edge::Literal).collect()
+    }
+}
+
+fn print_analysis(analysis: &analyze::Analysis) {
+    println!("empty: {}", analysis.empty);
+    match analysis.finite {
+        Some(size) => println!("finite: true ({} words)", size),
+        None => println!("finite: false"),
+    }
+    println!("universal: {}", analysis.universal);
+    println!("unreachable states: {:?}", analysis.unreachable);
+    println!("dead states: {:?}", analysis.dead);
+}
+
+fn print_words(min_dfa: &Dfa, max_len: usize) {
+    match lang::shortest(min_dfa) {
+        Some(word) => println!("shortest: \"{}\"", lang::to_string(&word)),
+        None => println!("shortest: none, the language is empty"),
+    }
+    for (len, count) in lang::count(min_dfa, max_len).iter().enumerate() {
+        println!("length {}: {} words", len, count);
+    }
+    for word in lang::enumerate(min_dfa, max_len) {
+        println!("\"{}\"", lang::to_string(&word));
+    }
 }
@@ -1,24 +1,70 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap};
 
 use petgraph::{adj::NodeIndex, prelude::DiGraph, stable_graph::IndexType, visit::EdgeRef};
 
-use crate::nfa::Edge;
+use crate::alphabet::Alphabet;
+use crate::canon::canonical;
+use crate::nfa::{Edge, Weight};
+use crate::trim::{trim, Trimmed};
 
-pub fn min_dfa(dfa: DiGraph<bool, Edge>) -> DiGraph<bool, Edge> {
-    let mut groups = Vec::<(Vec<NodeIndex>, bool)>::new();
-    let start = start_groups(&dfa);
-    if !start.0.is_empty() {
-        groups.push((start.0, true))
+pub fn min_dfa<N: Weight>(dfa: DiGraph<N, Edge>) -> DiGraph<N, Edge> {
+    min_dfa_trimmed(dfa).0
+}
+
+// same as min_dfa, also giving back the states the trimming pass removed
+pub fn min_dfa_trimmed<N: Weight>(dfa: DiGraph<N, Edge>) -> (DiGraph<N, Edge>, Trimmed) {
+    minimize(dfa, None)
+}
+
+// groups of dfa states (numbered as in the dfa before trimming) and their weight, so whether
+// they accept for plain automata
+pub type Partition<N = bool> = Vec<(Vec<NodeIndex>, N)>;
+
+// same as min_dfa, also giving back the partition after every refinement pass
+pub fn min_dfa_history<N: Weight>(dfa: DiGraph<N, Edge>) -> (DiGraph<N, Edge>, Vec<Partition<N>>) {
+    let mut history = Vec::new();
+    let (min_dfa, _) = minimize(dfa, Some(&mut history));
+    (min_dfa, history)
+}
+
+fn minimize<N: Weight>(
+    dfa: DiGraph<N, Edge>,
+    mut history: Option<&mut Vec<Partition<N>>>,
+) -> (DiGraph<N, Edge>, Trimmed) {
+    // partition refinement only sees useful states
+    let (dfa, trimmed) = trim(&dfa);
+    // trimming keeps the order of the states, so this maps trimmed ids back to the dfa ones
+    let kept = (0..(dfa.node_count() + trimmed.unreachable.len() + trimmed.dead.len()) as u32)
+        .filter(|n| !trimmed.unreachable.contains(n) && !trimmed.dead.contains(n))
+        .collect::<Vec<u32>>();
+    let mut record = |groups: &Partition<N>| {
+        if let Some(history) = history.as_mut() {
+            let partition = groups
+                .iter()
+                .map(|(g, accept)| {
+                    let mut g = g.iter().map(|n| kept[*n as usize]).collect::<Vec<u32>>();
+                    g.sort();
+                    (g, *accept)
+                })
+                .collect::<Partition<N>>();
+            if history.last() != Some(&partition) {
+                history.push(partition);
+            }
+        }
     };
-    if !start.1.is_empty() {
-        groups.push((start.1, false));
-    }
 
-    let mut consistent = Vec::<(Vec<NodeIndex>, bool)>::new();
+    let mut groups = start_groups(&dfa);
+
+    record(&groups);
+
+    let alphabet = Alphabet::new(&dfa); // transitions are compared per class of symbols
+    let mut consistent = Vec::<(Vec<NodeIndex>, N)>::new();
     while !groups.eq(&consistent) {
-        for (i, g) in groups.clone().into_iter().enumerate() {
-            let group_info = build_group(&dfa, g.0.clone(), &groups);
+        for g in groups.clone() {
+            let group_info = build_group(&dfa, &alphabet, g.0.clone(), &groups);
             if let Some(new_groups) = check_consistent_group(group_info.clone()) {
+                // earlier splits in this pass shift the indices, so find the group again
+                let i = groups.iter().position(|other| other == &g).unwrap();
                 groups.remove(i);
                 for new in new_groups {
                     groups.push((new, g.1))
@@ -31,44 +77,64 @@ pub fn min_dfa(dfa: DiGraph<bool, Edge>) -> DiGraph<bool, Edge> {
             }
         }
 
-
         groups.sort();
         consistent.sort();
+        record(&groups);
     }
 
-    build_dfa(&dfa, &groups)
+    // group order depends on how the dfa was numbered, so renumber to make the output stable
+    (canonical(&build_dfa(&dfa, &alphabet, &groups)), trimmed)
 }
 
-fn build_dfa(
-    dfa: &DiGraph<bool, Edge>,
-    groups: &Vec<(Vec<NodeIndex>, bool)>,
-) -> DiGraph<bool, Edge> {
-    let mut min_dfa = DiGraph::<bool, Edge>::new();
-    let mut mappings = Vec::<(NodeIndex, (Vec<NodeIndex>, bool))>::new();
-    for g in groups {
+fn build_dfa<N: Weight>(
+    dfa: &DiGraph<N, Edge>,
+    alphabet: &Alphabet,
+    groups: &[(Vec<NodeIndex>, N)],
+) -> DiGraph<N, Edge> {
+    let mut min_dfa = DiGraph::<N, Edge>::new();
+    let mut mappings = Vec::<(NodeIndex, (Vec<NodeIndex>, N))>::new();
+    // the group holding the dfa start goes first, so the start stays node 0 like in the nfa and dfa
+    let start = groups.iter().filter(|g| g.0.contains(&0));
+    for g in start.chain(groups.iter().filter(|g| !g.0.contains(&0))) {
         let node = min_dfa.add_node(g.1);
         mappings.push((node.index() as u32, g.clone()));
     }
 
+    // targets[node][class], None when the group has no transition on the class
+    let mut targets = vec![vec![None; alphabet.len()]; mappings.len()];
     for (node_index, group) in mappings.clone() {
-        let group_hash = build_group(dfa, group.0, groups)
+        let group_hash = build_group(dfa, alphabet, group.0, groups)
             .into_iter()
             .next()
             .unwrap()
             .1;
         for (c, group) in group_hash {
-            let mut map_iter = mappings.clone().into_iter();
-            let other = loop {
-                if let Some((other_index, other_group)) = map_iter.next() {
-                    if group.eq(&other_group.0) {
-                        break Some(other_index);
-                    }
-                } else {
-                    break None;
+            targets[node_index as usize][c] = mappings
+                .iter()
+                .find(|(_, other_group)| group.eq(&other_group.0))
+                .map(|(other_index, _)| *other_index);
+        }
+    }
+
+    // neighbouring classes that every state moves the same way on are one class of the min dfa,
+    // so a language gets the same labels however the pattern split up its symbols
+    let mut classes = Vec::<(Edge, usize)>::new(); // label and the first class in it
+    for (c, class) in alphabet.classes.iter().enumerate() {
+        if let Some((label, first)) = classes.last_mut() {
+            match join(label, class) {
+                Some(merged) if targets.iter().all(|t| t[c] == t[*first]) => {
+                    *label = merged;
+                    continue;
                 }
-            };
-            if let Some(other_index) = other {
-                min_dfa.add_edge((node_index).into(), other_index.into(), Edge::Literal(c));
+                _ => {}
+            }
+        }
+        classes.push((*class, c));
+    }
+    for (node_index, targets) in targets.iter().enumerate() {
+        for (label, first) in &classes {
+            if let Some(other_index) = targets[*first] {
+                min_dfa.add_edge(NodeIndex::new(node_index), other_index.into(), *label);
             }
         }
     }
@@ -76,10 +142,16 @@ fn build_dfa(
     min_dfa
 }
 
+// one range covering both, if there is no symbol between them
+fn join(a: &Edge, b: &Edge) -> Option<Edge> {
+    let ((lo, _), (_, hi)) = (a.bounds()?, b.bounds()?);
+    Edge::from_bounds(lo, hi, a.is_byte()).filter(|e| e.size() == a.size() + b.size())
+}
+
 fn check_consistent_group(
-    group_info: HashMap<u32, HashMap<char, Vec<u32>>>,
+    group_info: HashMap<u32, HashMap<usize, Vec<u32>>>,
 ) -> Option<Vec<Vec<u32>>> {
-    let mut groups = Vec::<(Vec<u32>, &HashMap<char, Vec<u32>>)>::new();
+    let mut groups = Vec::<(Vec<u32>, &HashMap<usize, Vec<u32>>)>::new();
     for (index, trans) in &group_info {
         let mut found = false;
         for group in &mut groups {
@@ -103,27 +175,22 @@ fn check_consistent_group(
     }
 }
 
-fn build_group(
-    dfa: &DiGraph<bool, Edge>,
+fn build_group<N: Weight>(
+    dfa: &DiGraph<N, Edge>,
+    alphabet: &Alphabet,
     group: Vec<NodeIndex>,
-    groups: &Vec<(Vec<NodeIndex>, bool)>,
-) -> HashMap<u32, HashMap<char, Vec<u32>>> {
-    let alphabet = find_literals(dfa);
-    let mut group_info = HashMap::<NodeIndex, HashMap<char, Vec<u32>>>::new();
+    groups: &[(Vec<NodeIndex>, N)],
+) -> HashMap<u32, HashMap<usize, Vec<u32>>> {
+    let mut group_info = HashMap::<NodeIndex, HashMap<usize, Vec<u32>>>::new();
     for index in group {
-        for a in &alphabet {
-
-            let mut info = if group_info.contains_key(&index) {
-                group_info.get(&index).unwrap().clone()
-            } else {
-                let info = HashMap::<char, Vec<u32>>::new();
-                group_info.insert(index, info);
-                group_info.get(&index).unwrap().clone()
-            };
+        group_info.entry(index).or_default(); // states without any transitions still need an entry
+        for a in 0..alphabet.len() {
+            let symbol = alphabet.representative(a);
+            let mut info = group_info.entry(index).or_default().clone();
             let edges = dfa
                 .edges((index).into())
                 .filter_map(|e| {
-                    if e.weight() == &Edge::Literal(*a) {
+                    if e.weight().contains(&symbol) {
                         Some(e.target())
                     } else {
                         None
@@ -131,28 +198,16 @@ fn build_group(
                 })
                 .collect::<Vec<_>>();
             if edges.is_empty() {
-                info.insert(*a, Vec::new());
+                info.insert(a, Vec::new());
                 group_info.insert(index, info);
             } else {
-                let mut groups_iter = groups.into_iter();
-                let group = loop {
-                    if let Some(g) = groups_iter.next() {
-                        let mut i = 0;
-
-                        while i < edges.len() && !g.0.contains(&(edges[i].index() as u32)) {
-                            i += 1;
-                        }
-                        if i == edges.len() {
-                            continue;
-                        } else {
-                            break Some(g);
-                        }
-                    }
-                };
+                let group = groups
+                    .iter()
+                    .find(|g| edges.iter().any(|e| g.0.contains(&(e.index() as u32))));
                 if let Some(g) = group {
-                    info.insert(*a, g.0.to_vec());
+                    info.insert(a, g.0.to_vec());
                 } else {
-                    info.insert(*a, Vec::new());
+                    info.insert(a, Vec::new());
                 }
 
                 group_info.insert(index, info);
@@ -163,28 +218,16 @@ fn build_group(
     group_info
 }
 
-fn find_literals(graph: &DiGraph<bool, Edge>) -> HashSet<char> {
-    graph
-        .edge_references()
-        .filter_map(|e| match e.weight() {
-            Edge::Epsilon => None,
-            Edge::Literal(c) => Some(c.to_owned()),
-        })
-        .collect::<HashSet<char>>()
-}
-
-fn start_groups(dfa: &DiGraph<bool, Edge>) -> (Vec<NodeIndex>, Vec<NodeIndex>) {
-    let mut accepts = Vec::<NodeIndex>::new();
-    let mut non_accepts = Vec::<NodeIndex>::new();
-
-    let mut ind = dfa.node_indices();
-    while let Some(index) = ind.next() {
-        if *(dfa.node_weight(index).unwrap()) {
-            accepts.push(index.index() as u32)
-        } else {
-            non_accepts.push(index.index() as u32)
-        }
+// one group per weight, so accepting and non accepting states for plain automata
+fn start_groups<N: Weight>(dfa: &DiGraph<N, Edge>) -> Vec<(Vec<NodeIndex>, N)> {
+    let mut groups = BTreeMap::<N, Vec<NodeIndex>>::new();
+    for index in dfa.node_indices() {
+        groups
+            .entry(*dfa.node_weight(index).unwrap())
+            .or_default()
+            .push(index.index() as u32);
     }
 
-    (accepts, non_accepts)
+    // accepting states first
+    groups.into_iter().rev().map(|(w, g)| (g, w)).collect()
 }
@@ -1,44 +1,247 @@
+use std::cmp::Ordering;
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+
 use petgraph::graph::{DiGraph, NodeIndex};
 use regex_syntax::{
     hir::{self, Hir},
-    Parser,
+    utf8::Utf8Sequences,
+    ParserBuilder,
 };
 
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+use crate::simplify::simplify;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Edge {
     Epsilon,
     Literal(char),
+    Byte(u8),
+    Range(char, char), // every char from the first to the last, for classes
+    ByteRange(u8, u8),
+    Look(Look), // epsilon edge that can only be taken when the assertion holds
+    Tag(u32), // epsilon edge that records the position, 2k - 2 opens capture group k, 2k - 1 closes it
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Look {
+    StartText,
+    EndText,
+    StartLine,
+    EndLine,
+    WordBoundary,
+    NotWordBoundary,
+}
+
+// what an assertion needs to know about the symbols around a position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Kind {
+    Word,
+    Newline,
+    Other,
+}
+
+impl Edge {
+    pub fn is_symbol(&self) -> bool {
+        self.bounds().is_some()
+    }
+
+    pub fn is_byte(&self) -> bool {
+        matches!(self, Edge::Byte(_) | Edge::ByteRange(_, _))
+    }
+
+    // the first and last symbol an edge reads, chars by their code point
+    pub fn bounds(&self) -> Option<(u32, u32)> {
+        match self {
+            Edge::Literal(c) => Some((*c as u32, *c as u32)),
+            Edge::Byte(b) => Some((*b as u32, *b as u32)),
+            Edge::Range(lo, hi) => Some((*lo as u32, *hi as u32)),
+            Edge::ByteRange(lo, hi) => Some((*lo as u32, *hi as u32)),
+            _ => None,
+        }
+    }
+
+    // the edge reading the symbols lo..=hi, a single one stays a literal or byte. None when there
+    // is no char in there, surrogates have a code point but aren't chars
+    pub fn from_bounds(lo: u32, hi: u32, bytes: bool) -> Option<Edge> {
+        if bytes {
+            let (lo, hi) = (u8::try_from(lo).ok()?, u8::try_from(hi).ok()?);
+            return match lo.cmp(&hi) {
+                Ordering::Less => Some(Edge::ByteRange(lo, hi)),
+                Ordering::Equal => Some(Edge::Byte(lo)),
+                Ordering::Greater => None,
+            };
+        }
+        let lo = char::from_u32(lo).or_else(|| char::from_u32(lo.max(0xE000)))?;
+        let hi = char::from_u32(hi).or_else(|| char::from_u32(hi.min(0xD7FF)))?;
+        match lo.cmp(&hi) {
+            Ordering::Less => Some(Edge::Range(lo, hi)),
+            Ordering::Equal => Some(Edge::Literal(lo)),
+            Ordering::Greater => None,
+        }
+    }
+
+    // whether the single symbol is one of the ones this edge reads
+    pub fn contains(&self, symbol: &Edge) -> bool {
+        match (self.bounds(), symbol.bounds()) {
+            (Some((lo, hi)), Some((c, _))) => {
+                self.is_byte() == symbol.is_byte() && lo <= c && c <= hi
+            }
+            _ => false,
+        }
+    }
+
+    // every symbol the edge reads, in order
+    pub fn symbols(&self) -> impl Iterator<Item = Edge> {
+        let (lo, hi) = self.bounds().unwrap_or((1, 0));
+        let bytes = self.is_byte();
+        (lo..=hi).filter_map(move |c| Edge::from_bounds(c, c, bytes))
+    }
+
+    pub fn first(&self) -> Edge {
+        self.symbols().next().unwrap_or(*self)
+    }
+
+    // how many symbols the edge reads
+    pub fn size(&self) -> u32 {
+        match self.bounds() {
+            Some((lo, hi)) if !self.is_byte() => {
+                let surrogates = hi.min(0xDFFF).saturating_sub(lo.max(0xD800)) + 1;
+                hi - lo + 1
+                    - if lo <= 0xDFFF && hi >= 0xD800 {
+                        surrogates
+                    } else {
+                        0
+                    }
+            }
+            Some((lo, hi)) => hi - lo + 1,
+            None => 0,
+        }
+    }
+
+    // tags don't matter for what is accepted, only for where the groups are
+    pub fn is_epsilon(&self) -> bool {
+        matches!(self, Edge::Epsilon | Edge::Tag(_))
+    }
+
+    // only meant for single symbols, the constructions split classes so they never mix kinds
+    pub fn kind(&self) -> Kind {
+        match self {
+            Edge::Literal('\n') | Edge::Byte(b'\n') => Kind::Newline,
+            Edge::Literal(c) if c.is_alphanumeric() || *c == '_' => Kind::Word,
+            Edge::Byte(b) if b.is_ascii_alphanumeric() || *b == b'_' => Kind::Word,
+            _ => Kind::Other,
+        }
+    }
+}
+
+// what the states of an automaton carry. plain automata only say if a state accepts, transducers
+// (transducer.rs) say what it writes too, and minimizing keeps states apart when that differs
+pub trait Weight: Copy + Ord + Hash + Debug + Default {
+    fn accepts(&self) -> bool;
+    // the weight of a dfa state made of nfa states with both weights
+    fn merge(self, other: Self) -> Self;
+}
+
+impl Weight for bool {
+    fn accepts(&self) -> bool {
+        *self
+    }
+
+    fn merge(self, other: Self) -> Self {
+        self || other
+    }
+}
+
+impl Look {
+    // prev/next are None at the start/end of the input
+    pub fn holds(&self, prev: Option<Kind>, next: Option<Kind>) -> bool {
+        let is_word = |k: Option<Kind>| k == Some(Kind::Word);
+        match self {
+            Look::StartText => prev.is_none(),
+            Look::EndText => next.is_none(),
+            Look::StartLine => prev.is_none() || prev == Some(Kind::Newline),
+            Look::EndLine => next.is_none() || next == Some(Kind::Newline),
+            Look::WordBoundary => is_word(prev) != is_word(next),
+            Look::NotWordBoundary => is_word(prev) == is_word(next),
+        }
+    }
+}
+
+impl fmt::Display for Edge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Edge::Epsilon => write!(f, "ε"),
+            Edge::Literal(c) => write!(f, "{}", c),
+            Edge::Byte(b) if b.is_ascii_graphic() => write!(f, "{}", *b as char),
+            Edge::Byte(b) => write!(f, "\\x{:02X}", b),
+            Edge::Range(lo, hi) => write!(f, "[{}-{}]", lo, hi),
+            Edge::ByteRange(lo, hi) => write!(f, "[{}-{}]", Edge::Byte(*lo), Edge::Byte(*hi)),
+            Edge::Look(Look::StartText) => write!(f, "^"),
+            Edge::Look(Look::EndText) => write!(f, "$"),
+            Edge::Look(Look::StartLine) => write!(f, "(?m:^)"),
+            Edge::Look(Look::EndLine) => write!(f, "(?m:$)"),
+            Edge::Look(Look::WordBoundary) => write!(f, "\\b"),
+            Edge::Look(Look::NotWordBoundary) => write!(f, "\\B"),
+            Edge::Tag(tag) => write!(f, "τ{}", tag),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Options {
+    pub bytes: bool,       // compile to an automaton over utf-8 bytes instead of chars
+    pub ignore_case: bool, // same as starting the pattern with (?i)
+    pub tags: bool,        // put tag edges around capture groups, for tdfa.rs
 }
 
-pub fn nfa(input: String) -> DiGraph<bool, Edge> {
+pub fn nfa(input: String, options: Options) -> Result<DiGraph<bool, Edge>, String> {
     let nfa = &mut DiGraph::<bool, Edge>::new();
-    let regex = Parser::new().parse(&input).unwrap();
+    let regex = simplify(&parse(&input, options)?, options);
     // dbg!(regex.clone());
-    sub_nfa(nfa, &regex, true).unwrap();
+    sub_nfa(nfa, &regex, true, options)?;
+
+    Ok(nfa.to_owned())
+}
 
-    nfa.to_owned()
+pub fn parse(input: &str, options: Options) -> Result<Hir, String> {
+    ParserBuilder::new()
+        .allow_invalid_utf8(options.bytes)
+        .case_insensitive(options.ignore_case)
+        .build()
+        .parse(input)
+        .map_err(|e| e.to_string())
 }
 
 fn sub_nfa(
     graph: &mut DiGraph<bool, Edge>,
     regex: &Hir,
     end_true: bool,
+    options: Options,
 ) -> Result<(NodeIndex, NodeIndex), &'static str> {
     match regex.to_owned().into_kind() {
         hir::HirKind::Literal(l) => match l {
+            hir::Literal::Unicode(c) if options.bytes => {
+                let mut buf = [0; 4];
+                Ok(byte_string(
+                    graph,
+                    c.encode_utf8(&mut buf).as_bytes(),
+                    end_true,
+                ))
+            }
             hir::Literal::Unicode(c) => {
                 let start = graph.add_node(false);
                 let end = accept(end_true, graph);
                 graph.add_edge(start, end, Edge::Literal(c));
                 Ok((start, end))
             }
+            hir::Literal::Byte(b) if options.bytes => Ok(byte_string(graph, &[b], end_true)),
             hir::Literal::Byte(_) => Err("Invalid literal format"),
         },
         hir::HirKind::Alternation(alts) => {
             let (start, end) = (graph.add_node(false), accept(end_true, graph));
             let mut alt_nfas: Vec<(NodeIndex, NodeIndex)> = Vec::new();
             for alt in alts.iter() {
-                alt_nfas.push(sub_nfa(graph, alt, false).unwrap());
+                alt_nfas.push(sub_nfa(graph, alt, false, options)?);
             }
 
             for nfa in alt_nfas {
@@ -50,16 +253,46 @@ fn sub_nfa(
         hir::HirKind::Repetition(rep) => match rep.kind {
             hir::RepetitionKind::ZeroOrOne => {
                 let (start, end) = (graph.add_node(false), accept(end_true, graph));
-                let (one_start, one_end) = sub_nfa(graph, rep.hir.as_ref(), false).unwrap();
+                let (one_start, one_end) = sub_nfa(graph, rep.hir.as_ref(), false, options)?;
 
-                graph.add_edge(start, end, Edge::Epsilon);
-                graph.add_edge(start, one_start, Edge::Epsilon);
+                // edges out of a state are in priority order, so greedy tries the one first
+                if rep.greedy {
+                    graph.add_edge(start, one_start, Edge::Epsilon);
+                    graph.add_edge(start, end, Edge::Epsilon);
+                } else {
+                    graph.add_edge(start, end, Edge::Epsilon);
+                    graph.add_edge(start, one_start, Edge::Epsilon);
+                }
                 graph.add_edge(one_end, end, Edge::Epsilon);
                 Ok((start, end))
             }
+            hir::RepetitionKind::ZeroOrMore if options.tags && rep.hir.is_match_empty() => {
+                // as (r+)? so an empty last round still sets the groups in r, like the regex crate
+                let one_or_more = Hir::repetition(hir::Repetition {
+                    kind: hir::RepetitionKind::OneOrMore,
+                    greedy: rep.greedy,
+                    hir: rep.hir,
+                });
+                let zero_or_one = Hir::repetition(hir::Repetition {
+                    kind: hir::RepetitionKind::ZeroOrOne,
+                    greedy: rep.greedy,
+                    hir: Box::new(one_or_more),
+                });
+                sub_nfa(graph, &zero_or_one, end_true, options)
+            }
+            hir::RepetitionKind::ZeroOrMore if !rep.greedy => {
+                // the way out has to come before the way back in, so it gets its own end
+                let (index, end) = (graph.add_node(false), accept(end_true, graph));
+                graph.add_edge(index, end, Edge::Epsilon);
+                let (inner_start, inner_end) = sub_nfa(graph, rep.hir.as_ref(), false, options)?;
+                graph.add_edge(index, inner_start, Edge::Epsilon);
+                graph.add_edge(inner_end, index, Edge::Epsilon);
+
+                Ok((index, end))
+            }
             hir::RepetitionKind::ZeroOrMore => {
                 let index = accept(end_true, graph);
-                let (inner_start, inner_end) = sub_nfa(graph, rep.hir.as_ref(), false).unwrap();
+                let (inner_start, inner_end) = sub_nfa(graph, rep.hir.as_ref(), false, options)?;
                 graph.add_edge(index, inner_start, Edge::Epsilon);
                 graph.add_edge(inner_end, index, Edge::Epsilon);
 
@@ -67,34 +300,49 @@ fn sub_nfa(
             }
             hir::RepetitionKind::OneOrMore => {
                 let (start, end) = (graph.add_node(false), accept(end_true, graph));
-                let (one_start, one_end) = sub_nfa(graph, rep.hir.as_ref(), false).unwrap();
+                let (one_start, one_end) = sub_nfa(graph, rep.hir.as_ref(), false, options)?;
 
                 graph.add_edge(start, one_start, Edge::Epsilon);
                 let more = graph.add_node(false);
 
                 graph.add_edge(one_end, more, Edge::Epsilon);
-                let (more_start, more_end) = sub_nfa(graph, rep.hir.as_ref(), false).unwrap();
+                let (more_start, more_end) = sub_nfa(graph, rep.hir.as_ref(), false, options)?;
 
-                graph.add_edge(more, more_start, Edge::Epsilon);
+                if rep.greedy {
+                    graph.add_edge(more, more_start, Edge::Epsilon);
+                    graph.add_edge(more, end, Edge::Epsilon);
+                } else {
+                    graph.add_edge(more, end, Edge::Epsilon);
+                    graph.add_edge(more, more_start, Edge::Epsilon);
+                }
                 graph.add_edge(more_end, more, Edge::Epsilon);
-                graph.add_edge(more, end, Edge::Epsilon);
 
                 Ok((start, end))
             }
             hir::RepetitionKind::Range(_) => Err("Range repetition is not allowed"),
         },
         hir::HirKind::Group(group) => match group.kind {
-            hir::GroupKind::CaptureIndex(_) => sub_nfa(graph, group.hir.as_ref(), end_true),
+            hir::GroupKind::CaptureIndex(index) if options.tags => {
+                let (start, end) = (graph.add_node(false), accept(end_true, graph));
+                let (inner_start, inner_end) = sub_nfa(graph, group.hir.as_ref(), false, options)?;
+                graph.add_edge(start, inner_start, Edge::Tag(2 * index - 2));
+                graph.add_edge(inner_end, end, Edge::Tag(2 * index - 1));
+                Ok((start, end))
+            }
+            hir::GroupKind::CaptureIndex(_) => {
+                sub_nfa(graph, group.hir.as_ref(), end_true, options)
+            }
             hir::GroupKind::CaptureName { name: _, index: _ } => {
                 Err("CaptureName groups not allowed")
             }
-            hir::GroupKind::NonCapturing => Err("NonCapturing groups not allowed"),
+            // flag groups like (?i:abc) come through as non capturing groups
+            hir::GroupKind::NonCapturing => sub_nfa(graph, group.hir.as_ref(), end_true, options),
         },
         hir::HirKind::Concat(concats) => {
             let (mut start, end) = (graph.add_node(false), accept(end_true, graph));
             let ret_start = start;
             for (i, concat) in concats.iter().enumerate() {
-                let (concat_start, concat_end) = sub_nfa(graph, concat, false).unwrap();
+                let (concat_start, concat_end) = sub_nfa(graph, concat, false, options)?;
                 graph.add_edge(start, concat_start, Edge::Epsilon);
                 start = concat_end;
                 if i == concats.len() - 1 {
@@ -104,9 +352,34 @@ fn sub_nfa(
             Ok((ret_start, end))
         }
         hir::HirKind::Empty => Err("Epislon not allowed"),
-        hir::HirKind::Class(_) => Err("Classes not allowed"),
-        hir::HirKind::Anchor(_) => Err("Anchor tags not allowed."),
-        hir::HirKind::WordBoundary(_) => Err("Word boundaries not allowed."),
+        hir::HirKind::Class(class) if options.bytes => Ok(byte_class(graph, &class, end_true)),
+        hir::HirKind::Class(class) => char_class(graph, &class, end_true),
+        hir::HirKind::Anchor(anchor) => Ok(look(
+            graph,
+            match anchor {
+                hir::Anchor::StartText => Look::StartText,
+                hir::Anchor::EndText => Look::EndText,
+                hir::Anchor::StartLine => Look::StartLine,
+                hir::Anchor::EndLine => Look::EndLine,
+            },
+            end_true,
+        )),
+        // a unicode word char is several bytes, none of which is a word char on its own
+        hir::HirKind::WordBoundary(
+            hir::WordBoundary::Unicode | hir::WordBoundary::UnicodeNegate,
+        ) if options.bytes => Err(
+            r"Unicode word boundaries don't work on bytes - use (?-u:\b) or (?-u:\B) with --bytes",
+        ),
+        hir::HirKind::WordBoundary(boundary) => Ok(look(
+            graph,
+            match boundary {
+                hir::WordBoundary::Unicode | hir::WordBoundary::Ascii => Look::WordBoundary,
+                hir::WordBoundary::UnicodeNegate | hir::WordBoundary::AsciiNegate => {
+                    Look::NotWordBoundary
+                }
+            },
+            end_true,
+        )),
     }
 }
 
@@ -117,3 +390,116 @@ pub fn accept(end_true: bool, graph: &mut DiGraph<bool, Edge>) -> NodeIndex {
         graph.add_node(false)
     }
 }
+
+fn look(graph: &mut DiGraph<bool, Edge>, look: Look, end_true: bool) -> (NodeIndex, NodeIndex) {
+    let start = graph.add_node(false);
+    let end = accept(end_true, graph);
+    graph.add_edge(start, end, Edge::Look(look));
+    (start, end)
+}
+
+// parallel edges from start to end, one per range of chars in the class
+fn char_class(
+    graph: &mut DiGraph<bool, Edge>,
+    class: &hir::Class,
+    end_true: bool,
+) -> Result<(NodeIndex, NodeIndex), &'static str> {
+    let ranges = class_ranges(class)?;
+    let start = graph.add_node(false);
+    let end = accept(end_true, graph);
+    for range in ranges {
+        graph.add_edge(start, end, range);
+    }
+    Ok((start, end))
+}
+
+// the ranges of chars in a class, as edges
+pub fn class_ranges(class: &hir::Class) -> Result<Vec<Edge>, &'static str> {
+    let ranges = match class {
+        hir::Class::Unicode(class) => class
+            .iter()
+            .map(|r| (r.start() as u32, r.end() as u32))
+            .collect::<Vec<(u32, u32)>>(),
+        hir::Class::Bytes(class) => {
+            if class.iter().any(|r| !r.end().is_ascii()) {
+                return Err("Non ASCII byte classes need byte mode");
+            }
+            class
+                .iter()
+                .map(|r| (r.start() as u32, r.end() as u32))
+                .collect::<Vec<(u32, u32)>>()
+        }
+    };
+    Ok(ranges
+        .into_iter()
+        .filter_map(|(lo, hi)| Edge::from_bounds(lo, hi, false))
+        .collect())
+}
+
+// chain of byte edges, used for a unicode literal expanded to its utf-8 encoding
+fn byte_string(
+    graph: &mut DiGraph<bool, Edge>,
+    bytes: &[u8],
+    end_true: bool,
+) -> (NodeIndex, NodeIndex) {
+    let start = graph.add_node(false);
+    let mut prev = start;
+    for (i, b) in bytes.iter().enumerate() {
+        let next = if i == bytes.len() - 1 {
+            accept(end_true, graph)
+        } else {
+            graph.add_node(false)
+        };
+        graph.add_edge(prev, next, Edge::Byte(*b));
+        prev = next;
+    }
+    (start, prev)
+}
+
+// every utf-8 byte sequence of the class becomes one path from start to end,
+// with an edge per byte range along the path
+fn byte_class(
+    graph: &mut DiGraph<bool, Edge>,
+    class: &hir::Class,
+    end_true: bool,
+) -> (NodeIndex, NodeIndex) {
+    let start = graph.add_node(false);
+    let end = accept(end_true, graph);
+    for ranges in class_byte_ranges(class) {
+        let mut prev = start;
+        for (i, (lo, hi)) in ranges.iter().enumerate() {
+            let next = if i == ranges.len() - 1 {
+                end
+            } else {
+                graph.add_node(false)
+            };
+            graph.add_edge(
+                prev,
+                next,
+                Edge::from_bounds(*lo as u32, *hi as u32, true).unwrap(),
+            );
+            prev = next;
+        }
+    }
+    (start, end)
+}
+
+// the utf-8 byte sequences of a class, each one a list of byte ranges
+pub fn class_byte_ranges(class: &hir::Class) -> Vec<Vec<(u8, u8)>> {
+    match class {
+        hir::Class::Unicode(class) => class
+            .iter()
+            .flat_map(|range| Utf8Sequences::new(range.start(), range.end()))
+            .map(|seq| {
+                seq.as_slice()
+                    .iter()
+                    .map(|r| (r.start, r.end))
+                    .collect::<Vec<(u8, u8)>>()
+            })
+            .collect(),
+        hir::Class::Bytes(class) => class
+            .iter()
+            .map(|range| vec![(range.start(), range.end())])
+            .collect(),
+    }
+}
@@ -0,0 +1,218 @@
+use petgraph::{prelude::DiGraph, visit::EdgeRef};
+
+use crate::dfa::SubsetStep;
+use crate::export::escape_xml;
+use crate::min_dfa::Partition;
+use crate::nfa::Edge;
+
+// step by step report of the subset construction and the minimization, as markdown or html
+
+pub struct Table {
+    pub title: String,
+    pub header: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+pub fn tables(
+    steps: &[SubsetStep],
+    history: &[Partition],
+    min_dfa: &DiGraph<bool, Edge>,
+) -> Vec<Table> {
+    vec![
+        subset_table(steps),
+        partition_table(history),
+        min_dfa_table(min_dfa),
+    ]
+}
+
+fn subset_table(steps: &[SubsetStep]) -> Table {
+    // the construction picks the next state out of a hash map, so the steps come in any order
+    let mut steps = steps.iter().collect::<Vec<&SubsetStep>>();
+    steps.sort_by_key(|step| step.dfa_state);
+    let mut rows = Vec::new();
+    for step in steps {
+        let state = if step.accept {
+            format!("{} (accept)", step.dfa_state)
+        } else {
+            step.dfa_state.to_string()
+        };
+        for (i, m) in step.moves.iter().enumerate() {
+            // only the first row of a state repeats the state and its subset
+            let (state, subset) = if i == 0 {
+                (state.clone(), set(&step.nfa_states))
+            } else {
+                (String::new(), String::new())
+            };
+            let target = match m.target {
+                Some(target) => target.to_string(),
+                None => String::from("-"),
+            };
+            rows.push(vec![
+                state,
+                subset,
+                m.class.to_string(),
+                set(&m.moved),
+                set(&m.closure),
+                target,
+            ]);
+        }
+        if step.moves.is_empty() {
+            rows.push(vec![
+                state,
+                set(&step.nfa_states),
+                String::new(),
+                String::new(),
+                String::new(),
+                String::from("-"),
+            ]);
+        }
+    }
+
+    Table {
+        title: String::from("Subset construction"),
+        header: strings(&[
+            "DFA state",
+            "NFA states",
+            "symbols",
+            "move",
+            "ε-closure",
+            "→",
+        ]),
+        rows,
+    }
+}
+
+fn partition_table(history: &[Partition]) -> Table {
+    let rows = history
+        .iter()
+        .enumerate()
+        .map(|(pass, partition)| {
+            let groups = partition
+                .iter()
+                .map(|(group, accept)| {
+                    if *accept {
+                        format!("{} (accept)", set(group))
+                    } else {
+                        set(group)
+                    }
+                })
+                .collect::<Vec<String>>();
+            vec![pass.to_string(), groups.join(" ")]
+        })
+        .collect();
+
+    Table {
+        title: String::from("Partition refinement"),
+        header: strings(&["pass", "groups of DFA states"]),
+        rows,
+    }
+}
+
+fn min_dfa_table(min_dfa: &DiGraph<bool, Edge>) -> Table {
+    let mut rows = Vec::new();
+    for n in min_dfa.node_indices() {
+        // one row per target, listing every symbol that goes there
+        let mut targets = Vec::<(usize, Vec<Edge>)>::new();
+        for edge in min_dfa.edges(n) {
+            match targets
+                .iter_mut()
+                .find(|(t, _)| *t == edge.target().index())
+            {
+                Some((_, symbols)) => symbols.push(*edge.weight()),
+                None => targets.push((edge.target().index(), vec![*edge.weight()])),
+            }
+        }
+        targets.sort();
+        let accept = min_dfa.node_weight(n).unwrap().to_string();
+        if targets.is_empty() {
+            rows.push(vec![
+                n.index().to_string(),
+                accept.clone(),
+                String::new(),
+                String::from("-"),
+            ]);
+        }
+        for (target, mut syms) in targets {
+            syms.sort_by_key(|s| s.bounds());
+            rows.push(vec![
+                n.index().to_string(),
+                accept.clone(),
+                symbols(&syms),
+                target.to_string(),
+            ]);
+        }
+    }
+
+    Table {
+        title: String::from("Minimal DFA"),
+        header: strings(&["state", "accept", "symbols", "→"]),
+        rows,
+    }
+}
+
+pub fn to_markdown(title: &str, tables: &[Table]) -> String {
+    let mut out = format!("# {}\n", title);
+    for table in tables {
+        out.push_str(&format!("\n## {}\n\n", table.title));
+        out.push_str(&markdown_row(&table.header));
+        out.push_str(&format!("|{}\n", "---|".repeat(table.header.len())));
+        for row in &table.rows {
+            out.push_str(&markdown_row(row));
+        }
+    }
+    out
+}
+
+fn markdown_row(cells: &[String]) -> String {
+    // pipes would end the cell early
+    let cells = cells
+        .iter()
+        .map(|c| c.replace('|', "\\|"))
+        .collect::<Vec<String>>();
+    format!("| {} |\n", cells.join(" | "))
+}
+
+pub fn to_html(title: &str, tables: &[Table]) -> String {
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n<style>table {{ border-collapse: collapse; }} td, th {{ border: 1px solid #999; padding: 2px 8px; font-family: monospace; }}</style>\n</head>\n<body>\n<h1>{0}</h1>\n",
+        escape_xml(title)
+    );
+    for table in tables {
+        out.push_str(&format!("<h2>{}</h2>\n<table>\n", escape_xml(&table.title)));
+        out.push_str(&html_row("th", &table.header));
+        for row in &table.rows {
+            out.push_str(&html_row("td", row));
+        }
+        out.push_str("</table>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn html_row(tag: &str, cells: &[String]) -> String {
+    let cells = cells
+        .iter()
+        .map(|c| format!("<{0}>{1}</{0}>", tag, escape_xml(c)))
+        .collect::<String>();
+    format!("<tr>{}</tr>\n", cells)
+}
+
+fn set(states: &[u32]) -> String {
+    let states = states
+        .iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<String>>();
+    format!("{{{}}}", states.join(", "))
+}
+
+fn symbols(symbols: &[Edge]) -> String {
+    let symbols = symbols
+        .iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<String>>();
+    symbols.join(" ")
+}
+
+fn strings(cells: &[&str]) -> Vec<String> {
+    cells.iter().map(|c| c.to_string()).collect()
+}
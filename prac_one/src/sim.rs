@@ -0,0 +1,127 @@
+use petgraph::{adj::NodeIndex, prelude::DiGraph};
+
+use crate::dfa::{check_accept, epsilon_closure, find_accepts, look_closure, move_literal};
+use crate::nfa::Edge;
+
+#[derive(Debug, Clone)]
+pub struct Step {
+    pub symbol: Option<Edge>, // None for the initial epsilon closure
+    pub states: Vec<NodeIndex>,
+}
+
+// Thompson style simulation of the nfa - keeps the set of active states instead of determinizing
+pub fn simulate(graph: &DiGraph<bool, Edge>, start: NodeIndex, input: &str) -> (bool, Vec<Step>) {
+    simulate_symbols(graph, start, input.chars().map(Edge::Literal).collect())
+}
+
+pub fn simulate_bytes(
+    graph: &DiGraph<bool, Edge>,
+    start: NodeIndex,
+    input: &[u8],
+) -> (bool, Vec<Step>) {
+    simulate_symbols(graph, start, input.iter().map(|b| Edge::Byte(*b)).collect())
+}
+
+fn simulate_symbols(
+    graph: &DiGraph<bool, Edge>,
+    start: NodeIndex,
+    input: Vec<Edge>,
+) -> (bool, Vec<Step>) {
+    let accepts = find_accepts(graph);
+    let mut trace = Vec::<Step>::new();
+
+    let closure = &mut Vec::<NodeIndex>::new();
+    let mut states = epsilon_closure(closure, graph, start).to_owned();
+    states.sort();
+    trace.push(Step {
+        symbol: None,
+        states: states.clone(),
+    });
+
+    let mut prev = None;
+    for c in &input {
+        // assertions are checked between the previous symbol and the one about to be read
+        let resolved = look_closure(states, graph, prev, Some(c.kind()));
+        states = match move_literal(c, resolved, graph) {
+            Some(mut next) => {
+                next.sort();
+                next
+            }
+            None => Vec::new(),
+        };
+        prev = Some(c.kind());
+        trace.push(Step {
+            symbol: Some(*c),
+            states: states.clone(),
+        });
+        if states.is_empty() {
+            break; // no active states left, the rest of the input can't be accepted
+        }
+    }
+
+    let accepted = trace.len() == input.len() + 1
+        && check_accept(&accepts, look_closure(states, graph, prev, None));
+    (accepted, trace)
+}
+
+// search mode - leftmost longest match of the nfa anywhere in the input, as (start, end) symbol offsets
+pub fn find(
+    graph: &DiGraph<bool, Edge>,
+    start: NodeIndex,
+    input: &[Edge],
+) -> Option<(usize, usize)> {
+    let accepts = find_accepts(graph);
+    for from in 0..=input.len() {
+        let mut prev = if from == 0 {
+            None
+        } else {
+            Some(input[from - 1].kind())
+        };
+        let closure = &mut Vec::<NodeIndex>::new();
+        let mut states = epsilon_closure(closure, graph, start).to_owned();
+        let mut longest = None;
+
+        let mut at = from;
+        loop {
+            let next = input.get(at).map(|c| c.kind());
+            let resolved = look_closure(states, graph, prev, next);
+            if check_accept(&accepts, resolved.clone()) {
+                longest = Some((from, at));
+            }
+            if at == input.len() {
+                break;
+            }
+            match move_literal(&input[at], resolved, graph) {
+                Some(next) => states = next,
+                None => break,
+            }
+            prev = next;
+            at += 1;
+        }
+
+        if longest.is_some() {
+            return longest;
+        }
+    }
+    None
+}
+
+pub fn print_trace(input: &str, accepted: bool, trace: &[Step]) {
+    for step in trace {
+        let states = step
+            .states
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+        match step.symbol {
+            None => println!("start -> {{{}}}", states),
+            Some(c) => println!("'{}'   -> {{{}}}", c, states),
+        }
+    }
+    if accepted {
+        println!("\"{}\" accepted", input);
+    } else {
+        println!("\"{}\" rejected", input);
+    }
+}
@@ -0,0 +1,215 @@
+use regex_syntax::hir::{self, Hir};
+
+use crate::nfa::Options;
+
+// rewrites the parsed regex into a smaller one for the same language before the thompson
+// construction, every node left over is a few states and epsilon edges less in the nfa:
+//  - groups that only group are dropped (capture groups too, unless Options::tags needs them)
+//  - concatenations and alternations inside their own kind are flattened into them
+//  - a repeated alternative is dropped, it can never match anything the first one doesn't
+//  - a repetition of a repetition is folded into one, (a*)* is a*, (a+)? is a* and so on
+//  - alternatives next to each other that start the same way share the start, ab|ac is a(b|c)
+// with tags the order alternatives and repetitions are tried in decides the capture groups,
+// so only the rewrites that keep that order are done then
+pub fn simplify(regex: &Hir, options: Options) -> Hir {
+    regroup(&simplified(regex, options))
+}
+
+// the simplified regex without any groups that don't capture, precedence is just the tree
+fn simplified(regex: &Hir, options: Options) -> Hir {
+    match regex.kind() {
+        hir::HirKind::Group(group) => match group.kind {
+            hir::GroupKind::CaptureIndex(_) if !options.tags => simplified(&group.hir, options),
+            hir::GroupKind::NonCapturing => simplified(&group.hir, options),
+            _ => Hir::group(hir::Group {
+                kind: group.kind.clone(),
+                hir: Box::new(simplified(&group.hir, options)),
+            }),
+        },
+        hir::HirKind::Concat(items) => {
+            let mut flat = Vec::new();
+            for item in items {
+                let item = simplified(item, options);
+                match item.kind() {
+                    hir::HirKind::Concat(inner) => flat.extend(inner.iter().cloned()),
+                    _ => flat.push(item),
+                }
+            }
+            Hir::concat(flat)
+        }
+        hir::HirKind::Alternation(alts) => {
+            let mut flat = Vec::<Hir>::new();
+            for alt in alts {
+                let alt = simplified(alt, options);
+                let inner = match alt.kind() {
+                    hir::HirKind::Alternation(inner) => inner.clone(),
+                    _ => vec![alt],
+                };
+                for alt in inner {
+                    if !flat.contains(&alt) {
+                        flat.push(alt);
+                    }
+                }
+            }
+            Hir::alternation(factor(flat, options))
+        }
+        hir::HirKind::Repetition(rep) => {
+            let inner = simplified(&rep.hir, options);
+            if let hir::HirKind::Repetition(inner_rep) = inner.kind() {
+                let keeps_order = rep.greedy == inner_rep.greedy && !has_captures(&inner_rep.hir);
+                if let Some(kind) = fold(&rep.kind, &inner_rep.kind) {
+                    if !options.tags || keeps_order {
+                        return Hir::repetition(hir::Repetition {
+                            kind,
+                            greedy: rep.greedy,
+                            hir: inner_rep.hir.clone(),
+                        });
+                    }
+                }
+            }
+            Hir::repetition(hir::Repetition {
+                kind: rep.kind.clone(),
+                greedy: rep.greedy,
+                hir: Box::new(inner),
+            })
+        }
+        _ => regex.clone(),
+    }
+}
+
+// the one repetition doing the same as outer over inner, ranges aren't supported anyway
+fn fold(outer: &hir::RepetitionKind, inner: &hir::RepetitionKind) -> Option<hir::RepetitionKind> {
+    use hir::RepetitionKind::*;
+    match (outer, inner) {
+        (Range(_), _) | (_, Range(_)) => None,
+        (ZeroOrOne, ZeroOrOne) => Some(ZeroOrOne),
+        (OneOrMore, OneOrMore) => Some(OneOrMore),
+        _ => Some(ZeroOrMore),
+    }
+}
+
+fn has_captures(regex: &Hir) -> bool {
+    match regex.kind() {
+        hir::HirKind::Group(group) => {
+            !matches!(group.kind, hir::GroupKind::NonCapturing) || has_captures(&group.hir)
+        }
+        hir::HirKind::Concat(items) | hir::HirKind::Alternation(items) => {
+            items.iter().any(has_captures)
+        }
+        hir::HirKind::Repetition(rep) => has_captures(&rep.hir),
+        _ => false,
+    }
+}
+
+// the items an alternative is a concatenation of
+fn items(regex: &Hir) -> Vec<Hir> {
+    match regex.kind() {
+        hir::HirKind::Concat(items) => items.clone(),
+        _ => vec![regex.clone()],
+    }
+}
+
+// only neighbours are put together, moving an alternative past another would change which one
+// is tried first
+fn factor(alts: Vec<Hir>, options: Options) -> Vec<Hir> {
+    let mut factored = Vec::new();
+    let mut i = 0;
+    while i < alts.len() {
+        let first = items(&alts[i]).remove(0);
+        let mut j = i + 1;
+        while j < alts.len() && items(&alts[j])[0] == first {
+            j += 1;
+        }
+        match factor_run(&alts[i..j], options) {
+            Some(alt) => factored.push(alt),
+            None => factored.extend_from_slice(&alts[i..j]),
+        }
+        i = j;
+    }
+    factored
+}
+
+// alternatives that share their first item as prefix(rest|rest|...), None if there is nothing
+// to share or it can't be done without changing the order
+fn factor_run(run: &[Hir], options: Options) -> Option<Hir> {
+    if run.len() < 2 {
+        return None;
+    }
+    let seqs = run.iter().map(items).collect::<Vec<Vec<Hir>>>();
+    let mut shared = 0;
+    while seqs
+        .iter()
+        .all(|s| s.len() > shared && s[shared] == seqs[0][shared])
+    {
+        shared += 1;
+    }
+
+    // the alternatives were deduplicated, so at most one of them is the prefix on its own
+    let rests = seqs
+        .iter()
+        .map(|s| Hir::concat(s[shared..].to_vec()))
+        .collect::<Vec<Hir>>();
+    let empty = rests.iter().position(|r| r.kind().is_empty());
+    let others = rests
+        .into_iter()
+        .filter(|r| !r.kind().is_empty())
+        .collect::<Vec<Hir>>();
+    let rest = match empty {
+        None => Hir::alternation(others),
+        // ab|a is a(b)?, and a|ab a(b)?? where the b is tried last
+        Some(at) if at == run.len() - 1 || at == 0 || !options.tags => {
+            Hir::repetition(hir::Repetition {
+                kind: hir::RepetitionKind::ZeroOrOne,
+                greedy: at != 0 || !options.tags,
+                hir: Box::new(Hir::alternation(others)),
+            })
+        }
+        Some(_) => return None,
+    };
+
+    let mut factored = seqs[0][..shared].to_vec();
+    factored.push(rest);
+    Some(simplified(&Hir::concat(factored), options))
+}
+
+// non capturing groups back where the printed pattern needs them, the nfa doesn't care
+fn regroup(regex: &Hir) -> Hir {
+    match regex.kind() {
+        hir::HirKind::Group(group) => Hir::group(hir::Group {
+            kind: group.kind.clone(),
+            hir: Box::new(regroup(&group.hir)),
+        }),
+        hir::HirKind::Concat(items) => Hir::concat(
+            items
+                .iter()
+                .map(|item| match item.kind() {
+                    hir::HirKind::Alternation(_) => group(regroup(item)),
+                    _ => regroup(item),
+                })
+                .collect(),
+        ),
+        hir::HirKind::Alternation(alts) => Hir::alternation(alts.iter().map(regroup).collect()),
+        hir::HirKind::Repetition(rep) => {
+            let inner = regroup(&rep.hir);
+            let inner = match rep.hir.kind() {
+                hir::HirKind::Concat(_)
+                | hir::HirKind::Alternation(_)
+                | hir::HirKind::Repetition(_) => group(inner),
+                _ => inner,
+            };
+            Hir::repetition(hir::Repetition {
+                kind: rep.kind.clone(),
+                greedy: rep.greedy,
+                hir: Box::new(inner),
+            })
+        }
+        _ => regex.clone(),
+    }
+}
+
+fn group(regex: Hir) -> Hir {
+    Hir::group(hir::Group {
+        kind: hir::GroupKind::NonCapturing,
+        hir: Box::new(regex),
+    })
+}
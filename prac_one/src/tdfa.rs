@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+use std::time::Instant;
+
+use petgraph::{graph::NodeIndex, prelude::DiGraph, visit::EdgeRef};
+
+use crate::alphabet::Alphabet;
+use crate::dfa::{BlowUp, Limits};
+use crate::nfa::Edge;
+
+// tagged dfa (Laurikari style) for pulling the capture groups out of a match.
+// the nfa has to be built with Options::tags so every group k is wrapped in tag edges 2k - 2 and
+// 2k - 1. a tdfa state is an ordered list of nfa states, highest priority first like the threads
+// of a backtracking matcher, and every entry in it has its own register per tag holding the last
+// position that tag was passed at. transitions carry the register operations, so matching is
+// still one table lookup per symbol, plus copying the registers along.
+// the leftmost-first (perl/regex crate) rules decide between matches: alternatives are tried left
+// to right and repetitions are greedy unless marked lazy
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Set,         // the tag was passed just now
+    Copy(usize), // keep the value of a register in the previous state
+    Clear,       // the tag hasn't been passed yet
+}
+
+#[derive(Debug, Clone)]
+pub struct TaggedState {
+    // index of the nfa state list entry whose registers hold the groups when the input ends
+    // here, None when this isn't an accepting state
+    pub accept: Option<usize>,
+    // class of symbols -> (target state, one op per register of the target)
+    pub transitions: HashMap<Edge, (usize, Vec<Op>)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TaggedDfa {
+    pub tags: usize,
+    pub start: Vec<Op>, // the registers of the start state (node 0) before reading anything
+    pub states: Vec<TaggedState>,
+}
+
+// one entry of a tdfa state in the making: the nfa state, the entry of the previous state it came
+// from and which tags were passed on the way
+type Thread = (u32, Option<usize>, Vec<u32>);
+
+pub fn tagged_dfa(nfa: &DiGraph<bool, Edge>) -> Result<TaggedDfa, String> {
+    tagged_dfa_limited(nfa, Limits::default())
+}
+
+// same as tagged_dfa, giving up like dfa::dfa_limited does when there are too many states
+pub fn tagged_dfa_limited(nfa: &DiGraph<bool, Edge>, limits: Limits) -> Result<TaggedDfa, String> {
+    let started = Instant::now();
+    if nfa
+        .edge_references()
+        .any(|e| matches!(e.weight(), Edge::Look(_)))
+    {
+        return Err(String::from(
+            "Assertions are not supported with capture groups",
+        ));
+    }
+    let tags = nfa
+        .edge_weights()
+        .filter_map(|e| match e {
+            Edge::Tag(tag) => Some(*tag as usize + 1),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0);
+    let alphabet = Alphabet::new(nfa);
+
+    // states are numbered in the order they are found and get their transitions in that order
+    let mut ids = HashMap::<Vec<u32>, usize>::new();
+    let mut lists = Vec::<Vec<u32>>::new();
+    let mut add = |list: Vec<u32>, lists: &mut Vec<Vec<u32>>| {
+        *ids.entry(list.clone()).or_insert_with(|| {
+            lists.push(list);
+            lists.len() - 1
+        })
+    };
+
+    let threads = closure(nfa, vec![(0, None, Vec::new())]);
+    let start = ops(&threads, tags);
+    add(threads.iter().map(|t| t.0).collect(), &mut lists);
+
+    let mut transitions = Vec::<HashMap<Edge, (usize, Vec<Op>)>>::new();
+    while transitions.len() < lists.len() {
+        let state = transitions.len();
+        if let Some(budget) = limits.time_budget {
+            if started.elapsed() > budget {
+                return Err(BlowUp::Time {
+                    budget,
+                    dfa_states: lists.len(),
+                    processed: state,
+                }
+                .to_string());
+            }
+        }
+        let list = lists[state].clone();
+        let mut moves = HashMap::new();
+        for (class, label) in alphabet.classes.iter().enumerate() {
+            // step every entry over the class, in priority order
+            let mut stepped = Vec::new();
+            for (i, s) in list.iter().enumerate() {
+                for target in priority_edges(nfa, *s)
+                    .filter(|(symbol, _)| symbol.contains(&alphabet.representative(class)))
+                    .map(|(_, target)| target)
+                {
+                    stepped.push((target, Some(i), Vec::new()));
+                }
+            }
+            let threads = closure(nfa, stepped);
+            if threads.is_empty() {
+                continue;
+            }
+            let ops = ops(&threads, tags);
+            let target = add(threads.iter().map(|t| t.0).collect(), &mut lists);
+            match limits.max_states {
+                Some(limit) if lists.len() > limit => {
+                    return Err(BlowUp::States {
+                        limit,
+                        nfa_states: nfa.node_count(),
+                        processed: state,
+                    }
+                    .to_string())
+                }
+                _ => {}
+            }
+            moves.insert(*label, (target, ops));
+        }
+        transitions.push(moves);
+    }
+
+    let states = lists
+        .iter()
+        .zip(transitions)
+        .map(|(list, transitions)| TaggedState {
+            accept: list.iter().position(|s| nfa[NodeIndex::new(*s as usize)]),
+            transitions,
+        })
+        .collect();
+    Ok(TaggedDfa {
+        tags,
+        start,
+        states,
+    })
+}
+
+// the edges out of a state in the order they were added, which is their priority
+// (petgraph hands them out newest first)
+fn priority_edges(nfa: &DiGraph<bool, Edge>, state: u32) -> impl Iterator<Item = (Edge, u32)> {
+    let mut edges = nfa
+        .edges(NodeIndex::new(state as usize))
+        .map(|e| (*e.weight(), e.target().index() as u32))
+        .collect::<Vec<(Edge, u32)>>();
+    edges.reverse();
+    edges.into_iter()
+}
+
+// follows the epsilon and tag edges depth first in priority order, an nfa state reached by a
+// higher priority thread already is dropped for the lower ones. only states with symbols going
+// out or that accept are kept, the rest don't change what happens next
+fn closure(nfa: &DiGraph<bool, Edge>, threads: Vec<Thread>) -> Vec<Thread> {
+    let mut seen = vec![false; nfa.node_count()];
+    let mut result = Vec::new();
+    for (state, from, tags) in threads {
+        let mut stack = vec![(state, tags)];
+        while let Some((state, tags)) = stack.pop() {
+            if seen[state as usize] {
+                continue;
+            }
+            seen[state as usize] = true;
+            let edges = priority_edges(nfa, state).collect::<Vec<(Edge, u32)>>();
+            if nfa[NodeIndex::new(state as usize)] || edges.iter().any(|(e, _)| e.is_symbol()) {
+                result.push((state, from, tags.clone()));
+            }
+            // pushed in reverse so the highest priority edge is popped first
+            for (edge, target) in edges.into_iter().rev() {
+                match edge {
+                    Edge::Epsilon => stack.push((target, tags.clone())),
+                    Edge::Tag(tag) => {
+                        let mut tags = tags.clone();
+                        tags.push(tag);
+                        stack.push((target, tags));
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    result
+}
+
+// register j * tags + t is tag t of entry j
+fn ops(threads: &[Thread], tags: usize) -> Vec<Op> {
+    let mut ops = Vec::with_capacity(threads.len() * tags);
+    for (_, from, passed) in threads {
+        for tag in 0..tags {
+            ops.push(if passed.contains(&(tag as u32)) {
+                Op::Set
+            } else if let Some(from) = from {
+                Op::Copy(from * tags + tag)
+            } else {
+                Op::Clear
+            });
+        }
+    }
+    ops
+}
+
+impl TaggedDfa {
+    pub fn groups(&self) -> usize {
+        self.tags / 2
+    }
+
+    // the span of every group (0 is the whole input) if the whole input is accepted, positions
+    // count symbols so they are byte offsets in byte mode and char offsets otherwise
+    pub fn captures(&self, input: &[Edge]) -> Option<Vec<Option<(usize, usize)>>> {
+        let mut state = 0;
+        let mut registers = apply(&self.start, &[], 0);
+        for (pos, symbol) in input.iter().enumerate() {
+            let (target, ops) = self.states[state]
+                .transitions
+                .iter()
+                .find(|(class, _)| class.contains(symbol))
+                .map(|(_, next)| next)?;
+            registers = apply(ops, &registers, pos + 1);
+            state = *target;
+        }
+
+        let entry = self.states[state].accept?;
+        let mut groups = vec![Some((0, input.len()))];
+        for group in 0..self.groups() {
+            let open = registers[entry * self.tags + 2 * group];
+            let close = registers[entry * self.tags + 2 * group + 1];
+            groups.push(open.zip(close));
+        }
+        Some(groups)
+    }
+}
+
+fn apply(ops: &[Op], registers: &[Option<usize>], pos: usize) -> Vec<Option<usize>> {
+    ops.iter()
+        .map(|op| match op {
+            Op::Set => Some(pos),
+            Op::Copy(from) => registers[*from],
+            Op::Clear => None,
+        })
+        .collect()
+}
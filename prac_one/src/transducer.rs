@@ -0,0 +1,142 @@
+use petgraph::{graph::NodeIndex, prelude::DiGraph, visit::EdgeRef};
+
+use crate::combine::union;
+use crate::dfa::dfa;
+use crate::min_dfa::min_dfa;
+use crate::nfa::{Edge, Options, Weight};
+
+// rewriting rules (pattern => output) compiled with the same nfa -> dfa -> min dfa steps as a
+// plain regex, only the states carry which rule they end instead of just true or false. that
+// makes it a Moore machine: what gets written depends on the state reached. an output on a
+// transition is the same thing as one on the state it leads to, once states that would write
+// different things are kept apart, which minimizing does since it starts from one group per rule
+
+// the rule a state ends, the earliest one wins when a state ends more than one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Rule(pub Option<u32>);
+
+impl Weight for Rule {
+    fn accepts(&self) -> bool {
+        self.0.is_some()
+    }
+
+    fn merge(self, other: Self) -> Self {
+        match (self.0, other.0) {
+            (Some(a), Some(b)) => Rule(Some(a.min(b))),
+            (a, b) => Rule(a.or(b)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Transducer {
+    pub dfa: DiGraph<Rule, Edge>, // minimal, start at node 0
+    pub outputs: Vec<String>,     // what each rule writes
+    bytes: bool,
+}
+
+impl Transducer {
+    pub fn new(rules: &[(String, String)], options: Options) -> Result<Self, String> {
+        let mut automata = Vec::new();
+        for (i, (pattern, _)) in rules.iter().enumerate() {
+            let nfa =
+                crate::build_nfa(pattern, options).map_err(|e| format!("Rule {}: {}", i + 1, e))?;
+            let rule = Rule(Some(i as u32));
+            automata.push(nfa.map(
+                |_, accept| if *accept { rule } else { Rule(None) },
+                |_, e| *e,
+            ));
+        }
+        Ok(Self {
+            dfa: min_dfa(dfa(&union(&automata), 0)),
+            outputs: rules.iter().map(|(_, output)| output.clone()).collect(),
+            bytes: options.bytes,
+        })
+    }
+
+    // goes through the input taking the longest match of any rule at each position and writing
+    // its output instead, symbols no rule matches are copied over
+    pub fn rewrite(&self, input: &str) -> String {
+        let symbols = self.symbols(input);
+        let mut output = Vec::<u8>::new();
+        let mut at = 0;
+        while at < symbols.len() {
+            match self.longest(&symbols[at..]) {
+                Some((len, rule)) => {
+                    output.extend(self.outputs[rule as usize].as_bytes());
+                    at += len;
+                }
+                None => {
+                    match symbols[at] {
+                        Edge::Literal(c) => output.extend(c.to_string().as_bytes()),
+                        Edge::Byte(b) => output.push(b),
+                        _ => {}
+                    }
+                    at += 1;
+                }
+            }
+        }
+        String::from_utf8_lossy(&output).into_owned()
+    }
+
+    // runs the whole input as a Moore machine, every state reached writes the output of the rule
+    // it ends (if any). None if the input leaves the automaton
+    pub fn translate(&self, input: &str) -> Option<String> {
+        let mut output = String::new();
+        let mut state = NodeIndex::new(0);
+        for symbol in self.symbols(input) {
+            state = self.step(state, &symbol)?;
+            if let Rule(Some(rule)) = self.dfa[state] {
+                output.push_str(&self.outputs[rule as usize]);
+            }
+        }
+        Some(output)
+    }
+
+    // length and rule of the longest non empty match at the start of symbols
+    fn longest(&self, symbols: &[Edge]) -> Option<(usize, u32)> {
+        let mut state = NodeIndex::new(0);
+        let mut longest = None;
+        for (i, symbol) in symbols.iter().enumerate() {
+            match self.step(state, symbol) {
+                Some(next) => state = next,
+                None => break,
+            }
+            if let Rule(Some(rule)) = self.dfa[state] {
+                longest = Some((i + 1, rule));
+            }
+        }
+        longest
+    }
+
+    fn step(&self, state: NodeIndex, symbol: &Edge) -> Option<NodeIndex> {
+        self.dfa
+            .edges(state)
+            .find(|e| e.weight().contains(symbol))
+            .map(|e| e.target())
+    }
+
+    fn symbols(&self, input: &str) -> Vec<Edge> {
+        if self.bytes {
+            input.bytes().map(Edge::Byte).collect()
+        } else {
+            input.chars().map(Edge::Literal).collect()
+        }
+    }
+}
+
+// one rule per line as `pattern => output`, blank lines and lines starting with # are skipped
+pub fn parse_rules(source: &str) -> Result<Vec<(String, String)>, String> {
+    let mut rules = Vec::new();
+    for (i, line) in source.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (pattern, output) = line
+            .rsplit_once(" =>")
+            .ok_or_else(|| format!("Line {}: expected `pattern => output`", i + 1))?;
+        let output = output.strip_prefix(' ').unwrap_or(output);
+        rules.push((pattern.to_string(), output.to_string()));
+    }
+    Ok(rules)
+}
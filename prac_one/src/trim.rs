@@ -0,0 +1,46 @@
+use petgraph::{prelude::DiGraph, visit::EdgeRef};
+
+use crate::analyze::{dead_states, unreachable_states};
+use crate::nfa::{Edge, Weight};
+
+#[derive(Debug, Clone, Default)]
+pub struct Trimmed {
+    pub unreachable: Vec<u32>,
+    pub dead: Vec<u32>,
+}
+
+// drops the states that can't be reached from the start or can't reach an accept state,
+// the start (node 0) is always kept so an empty language still has a start state
+pub fn trim<N: Weight>(dfa: &DiGraph<N, Edge>) -> (DiGraph<N, Edge>, Trimmed) {
+    let unreachable = unreachable_states(dfa);
+    let dead = dead_states(dfa)
+        .into_iter()
+        .filter(|n| !unreachable.contains(n))
+        .collect::<Vec<u32>>();
+
+    // rebuild instead of remove_node, which would swap indices around
+    let mut trimmed = DiGraph::<N, Edge>::new();
+    let mut mapping = vec![None; dfa.node_count()];
+    for n in dfa.node_indices() {
+        let index = n.index() as u32;
+        if index == 0 || !(unreachable.contains(&index) || dead.contains(&index)) {
+            mapping[n.index()] = Some(trimmed.add_node(*dfa.node_weight(n).unwrap()));
+        }
+    }
+    for e in dfa.edge_references() {
+        if let (Some(source), Some(target)) =
+            (mapping[e.source().index()], mapping[e.target().index()])
+        {
+            trimmed.add_edge(source, target, *e.weight());
+        }
+    }
+
+    let removed = |n: &u32| mapping[*n as usize].is_none();
+    (
+        trimmed,
+        Trimmed {
+            unreachable: unreachable.iter().copied().filter(removed).collect(),
+            dead: dead.iter().copied().filter(removed).collect(),
+        },
+    )
+}
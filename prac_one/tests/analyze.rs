@@ -0,0 +1,51 @@
+// what analyze says about the language of a dfa and which of its states are useless
+use prac_1::analyze::{analyze, Analysis};
+use prac_1::{compile, Dfa, Edge, Options};
+
+fn analysis(pattern: &str) -> Analysis {
+    analyze(&compile(pattern, Options::default()).unwrap().dfa)
+}
+
+#[test]
+fn empty_language() {
+    let empty = analysis("a^b");
+    assert!(empty.empty);
+    assert_eq!(empty.finite, Some(0));
+    assert!(!empty.universal);
+    assert!(!analysis("a*").empty);
+    assert!(!analysis("abc").empty);
+}
+
+#[test]
+fn finite_languages_count_their_words() {
+    assert_eq!(analysis("abc").finite, Some(1));
+    assert_eq!(analysis("ab?c").finite, Some(2));
+    assert_eq!(analysis("(a|b)(a|b)c?").finite, Some(8));
+    assert_eq!(analysis("a*b").finite, None);
+    assert_eq!(analysis("a(b|c)+").finite, None);
+}
+
+#[test]
+fn universal_over_its_own_symbols() {
+    assert!(analysis("a*").universal);
+    assert!(analysis("(a|b)*").universal);
+    assert!(analysis("(a*b*)*").universal);
+    assert!(!analysis("(a|b)*a").universal);
+    assert!(!analysis("(a|b)+").universal);
+    assert!(!analysis("a*|b*").universal);
+}
+
+#[test]
+fn unreachable_and_dead_states() {
+    // 0 -a-> 1 (accept), 2 -a-> 1 can't be reached and 0 -b-> 3 never accepts
+    let mut dfa = Dfa::new();
+    let states = [false, true, false, false].map(|accept| dfa.add_node(accept));
+    dfa.add_edge(states[0], states[1], Edge::Literal('a'));
+    dfa.add_edge(states[2], states[1], Edge::Literal('a'));
+    dfa.add_edge(states[0], states[3], Edge::Literal('b'));
+    let analysis = analyze(&dfa);
+    assert_eq!(analysis.unreachable, [2]);
+    assert_eq!(analysis.dead, [3]);
+    assert!(!analysis.empty);
+    assert_eq!(analysis.finite, Some(1));
+}
@@ -0,0 +1,118 @@
+// anchors and word boundaries have to mean what they mean in the regex crate, both for whole
+// words through every stage and for sim::find searching inside a text
+use regex::Regex;
+
+use prac_1::dense::DenseDfa;
+use prac_1::sim::{find, simulate, simulate_bytes};
+use prac_1::{build_nfa, compile, Edge, Options};
+
+const BYTES: Options = Options {
+    bytes: true,
+    ignore_case: false,
+    tags: false,
+};
+
+const WORDS: [&str; 14] = [
+    "", "a", "ab", "b", "a b", "ab b", "aab", "a\nb", "b\na", "ab\n", "\nab", "a\n", "é", "éa b",
+];
+
+fn agrees(pattern: &str, options: Options) {
+    let expected = Regex::new(&format!(r"\A(?:{})\z", pattern)).unwrap();
+    let compiled = compile(pattern, options).unwrap();
+    let dense = DenseDfa::new(&compiled.min_dfa);
+    for word in WORDS {
+        let want = expected.is_match(word);
+        let (nfa, _) = if options.bytes {
+            simulate_bytes(&compiled.nfa, 0, word.as_bytes())
+        } else {
+            simulate(&compiled.nfa, 0, word)
+        };
+        assert_eq!(nfa, want, "nfa: {} on {:?}", pattern, word);
+        assert_eq!(
+            dense.is_match(word),
+            want,
+            "min dfa: {} on {:?}",
+            pattern,
+            word
+        );
+    }
+}
+
+// leftmost longest and the regex crate's leftmost first only agree on greedy patterns with one
+// way to match, which is all these are
+fn finds(pattern: &str, text: &str) {
+    let expected = Regex::new(pattern).unwrap().find(text).map(|m| {
+        (
+            text[..m.start()].chars().count(),
+            text[..m.end()].chars().count(),
+        )
+    });
+    let nfa = build_nfa(pattern, Options::default()).unwrap();
+    let symbols = text.chars().map(Edge::Literal).collect::<Vec<Edge>>();
+    assert_eq!(
+        find(&nfa, 0, &symbols),
+        expected,
+        "{} in {:?}",
+        pattern,
+        text
+    );
+}
+
+#[test]
+fn text_anchors() {
+    for pattern in ["^ab", "ab$", "^$", "a^b", "a$|^b", "(^a|b)b*$"] {
+        agrees(pattern, Options::default());
+        agrees(pattern, BYTES);
+    }
+}
+
+#[test]
+fn line_anchors() {
+    for pattern in [
+        "(?m)^a$",
+        "(?m)^a\\nb$",
+        "(?m)a$\\n^b",
+        "(?m)^b|a$\\n",
+        "(?m)$\\n^ab",
+    ] {
+        agrees(pattern, Options::default());
+        agrees(pattern, BYTES);
+    }
+}
+
+#[test]
+fn word_boundaries() {
+    for pattern in [
+        "\\bab\\b",
+        "a\\B",
+        "\\Bb",
+        "a\\b b",
+        "\\b(a|é)+\\b[ ab]*",
+        "é?a\\B",
+    ] {
+        agrees(pattern, Options::default());
+    }
+    for pattern in ["(?-u:\\b)ab(?-u:\\b)", "a(?-u:\\B)", "a(?-u:\\b) b"] {
+        agrees(pattern, BYTES);
+    }
+}
+
+#[test]
+fn unicode_word_boundaries_need_char_mode() {
+    for pattern in ["\\bé\\b", "a\\B"] {
+        let error = build_nfa(pattern, BYTES).unwrap_err();
+        assert!(error.contains("(?-u:\\b)"), "{}", error);
+    }
+}
+
+#[test]
+fn find_agrees_with_regex_crate() {
+    finds("\\bab\\b", "cab ab");
+    finds("\\Ba+", "xaa a");
+    finds("(?m)^b+", "ab\nbb");
+    finds("(?m)a$", "ab\na\n");
+    finds("a$", "aa\na");
+    finds("^b", "ab");
+    finds("\\bé+", "aé éé");
+    finds("\\b", "");
+}
@@ -0,0 +1,88 @@
+// the batch command as a user runs it: files named after the patterns, a summary table and the
+// exit code saying if every row worked
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+// a fresh directory per test, so tests running at the same time don't see each other's files
+fn scratch(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("prac_1_batch_{}_{}", std::process::id(), test));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn batch(dir: &Path, file: &str, source: &str) -> Output {
+    let path = dir.join(file);
+    fs::write(&path, source).unwrap();
+    Command::new(env!("CARGO_BIN_EXE_prac_1"))
+        .arg("batch")
+        .arg(&path)
+        .arg("--out-dir")
+        .arg(dir.join("out"))
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn lines_are_named_by_line_number() {
+    let dir = scratch("lines");
+    let output = batch(&dir, "patterns.txt", "(a|b)*abb\n\n# skipped\na+\n");
+    assert_eq!(output.status.code(), Some(0));
+    let summary = String::from_utf8(output.stdout).unwrap();
+    assert!(summary.starts_with("name"), "{}", summary);
+    assert!(summary.contains("line_1"), "{}", summary);
+    assert!(summary.contains("line_4"), "{}", summary);
+    assert!(!summary.contains("line_3"), "{}", summary);
+
+    let mut files = fs::read_dir(dir.join("out"))
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .collect::<Vec<String>>();
+    files.sort();
+    assert_eq!(files, ["line_1.xml", "line_4.xml"]);
+    let xml = fs::read_to_string(dir.join("out").join("line_1.xml")).unwrap();
+    assert!(xml.starts_with("<mindfa>"));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn toml_names_the_files() {
+    let dir = scratch("toml");
+    let output = batch(
+        &dir,
+        "patterns.toml",
+        "abb = \"(a|b)*abb\"\nword = \"[a-c]+\"\n",
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert!(dir.join("out").join("abb.xml").exists());
+    assert!(dir.join("out").join("word.xml").exists());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn names_cant_leave_the_out_dir() {
+    let dir = scratch("traversal");
+    let source = "\"../escaped\" = \"a\"\n\"..\" = \"b\"\n\"a\\\\b\" = \"c\"\nfine = \"d\"\n";
+    let output = batch(&dir, "patterns.toml", source);
+    assert_eq!(output.status.code(), Some(2));
+    let summary = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(summary.matches("Invalid name").count(), 3, "{}", summary);
+    assert!(!dir.join("escaped.xml").exists());
+    assert!(!dir.join("out.xml").exists());
+    let files = fs::read_dir(dir.join("out")).unwrap().count();
+    assert_eq!(files, 1);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn a_failed_row_exits_with_2() {
+    let dir = scratch("failed");
+    let output = batch(&dir, "patterns.txt", "ab\na**\n");
+    assert_eq!(output.status.code(), Some(2));
+    let summary = String::from_utf8(output.stdout).unwrap();
+    assert!(summary.contains("Invalid regex string"), "{}", summary);
+    assert!(dir.join("out").join("line_1.xml").exists());
+    assert!(!dir.join("out").join("line_2.xml").exists());
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,77 @@
+// capture groups from the tagged dfa have to be where the regex crate puts them
+use prac_1::tdfa::{tagged_dfa, TaggedDfa};
+use prac_1::{build_nfa, Edge, Options};
+use regex::Regex;
+
+fn tdfa(pattern: &str, options: Options) -> TaggedDfa {
+    let options = Options {
+        tags: true,
+        ..options
+    };
+    tagged_dfa(&build_nfa(pattern, options).unwrap()).unwrap()
+}
+
+fn check(pattern: &str, words: &[&str]) {
+    let tdfa = tdfa(pattern, Options::default());
+    let expected = Regex::new(&format!("^(?:{})$", pattern)).unwrap();
+    for word in words {
+        let symbols = word.chars().map(Edge::Literal).collect::<Vec<Edge>>();
+        let want = expected.captures(word).map(|caps| {
+            caps.iter()
+                .map(|m| m.map(|m| (m.start(), m.end())))
+                .collect::<Vec<Option<(usize, usize)>>>()
+        });
+        assert_eq!(tdfa.captures(&symbols), want, "{} on {:?}", pattern, word);
+    }
+}
+
+#[test]
+fn agrees_with_regex_crate() {
+    check("(a+)(b*)(ab|c)?", &["aab", "aaabab", "ac", "b", ""]);
+    check("(a|ab)(c|bcd)(d*)", &["abcd", "acd", "abcdd"]);
+    check(
+        "([a-z]+)@([a-z]+)\\.(com|org)",
+        &["joe@example.com", "x@y.org", "x@y"],
+    );
+    check("((a)|b)*", &["ab", "ba", "bab", ""]);
+    check("(a*)(a*)", &["", "a", "aaa"]);
+    check("(a+?)(a*)", &["a", "aaa"]);
+    check("(a??)(a*)", &["", "a", "aa"]);
+    check("(a*?)(a*)", &["", "a", "aaa"]);
+    check("(a|b)*?(b+)", &["b", "abb", "bab"]);
+    check("(x(y)?)+", &["x", "xy", "xyx", "xxy"]);
+    check("(?:(a)|(b))+", &["ab", "ba", "aab"]);
+    check("(a?)*", &["", "a", "aa"]);
+}
+
+#[test]
+fn byte_mode_counts_bytes() {
+    let bytes = Options {
+        bytes: true,
+        ..Options::default()
+    };
+    let tdfa = tdfa("(é+)(x)", bytes);
+    let symbols = "ééx".bytes().map(Edge::Byte).collect::<Vec<Edge>>();
+    assert_eq!(
+        tdfa.captures(&symbols),
+        Some(vec![Some((0, 5)), Some((0, 4)), Some((4, 5))])
+    );
+}
+
+#[test]
+fn no_groups_and_no_match() {
+    let tdfa = tdfa("ab*", Options::default());
+    assert_eq!(tdfa.groups(), 0);
+    let symbols = "abb".chars().map(Edge::Literal).collect::<Vec<Edge>>();
+    assert_eq!(tdfa.captures(&symbols), Some(vec![Some((0, 3))]));
+    assert_eq!(tdfa.captures(&symbols[1..]), None);
+}
+
+#[test]
+fn assertions_are_rejected() {
+    let options = Options {
+        tags: true,
+        ..Options::default()
+    };
+    assert!(tagged_dfa(&build_nfa("(a)$", options).unwrap()).is_err());
+}
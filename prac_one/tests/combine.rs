@@ -0,0 +1,94 @@
+// every operation has to give the same min dfa as a regex for the resulting language
+use prac_1::canon::isomorphic;
+use prac_1::combine::{concat, prefix, reverse, star, suffix, union};
+use prac_1::dfa::dfa;
+use prac_1::import::from_xml;
+use prac_1::min_dfa::min_dfa;
+use prac_1::{build_nfa, compile, to_xml, Dfa, Options};
+
+fn min(pattern: &str) -> Dfa {
+    compile(pattern, Options::default()).unwrap().min_dfa
+}
+
+// through xml and back, like combine does with the files compile wrote
+fn load(pattern: &str) -> Dfa {
+    from_xml(&to_xml(&min(pattern), "mindfa"), false).unwrap()
+}
+
+fn same(nfa: Dfa, pattern: &str) {
+    assert!(
+        isomorphic(&min_dfa(dfa(&nfa, 0)), &min(pattern)),
+        "{}",
+        pattern
+    );
+}
+
+#[test]
+fn xml_round_trip() {
+    for pattern in [
+        "(a|b)*abb",
+        "a+b?c*",
+        "[a-c]+x|b",
+        "<&>| x",
+        "é|\\n",
+        "[^a]x|\\d+",
+    ] {
+        assert!(isomorphic(&load(pattern), &min(pattern)), "{}", pattern);
+    }
+    let nfa = compile("(a|b)*", Options::default()).unwrap().nfa;
+    let loaded = from_xml(&to_xml(&nfa, "nfa"), false).unwrap();
+    assert_eq!(to_xml(&loaded, "nfa"), to_xml(&nfa, "nfa"));
+
+    let bytes = Options {
+        bytes: true,
+        ..Options::default()
+    };
+    let min_dfa = compile("é|a b|[^a]", bytes).unwrap().min_dfa;
+    let loaded = from_xml(&to_xml(&min_dfa, "mindfa"), true).unwrap();
+    assert!(isomorphic(&loaded, &min_dfa));
+}
+
+#[test]
+fn bad_xml() {
+    assert!(from_xml("<mindfa><states></states></mindfa>", false).is_err());
+    assert!(from_xml(
+        "<mindfa><states><0>maybe</0></states><transitions></transitions></mindfa>",
+        false
+    )
+    .is_err());
+    assert!(from_xml(
+        "<mindfa><states><0>true</0></states><transitions><0><1>a</1></0></transitions></mindfa>",
+        false
+    )
+    .is_err());
+    assert!(from_xml(
+        "<mindfa><states><0>true</0></states><transitions><0><0>[z-a]</0></0></transitions></mindfa>",
+        false
+    )
+    .is_err());
+    assert!(from_xml("<mindfa><states>", false).is_err());
+}
+
+#[test]
+fn operations() {
+    same(reverse(&load("ab*c")), "cb*a");
+    same(reverse(&load("(a|b)*abb")), "bba(a|b)*");
+    same(prefix(&load("(a|b)*abb")), "(a|b)*");
+    same(prefix(&load("abc")), "(a(bc?)?)?");
+    same(suffix(&load("abc")), "((a?b)?c)?");
+    same(suffix(&load("ab*c")), "(ab*c|b*c)?");
+    same(concat(&[load("ab"), load("c+")]), "abc+");
+    same(concat(&[load("a"), load("b*"), load("a")]), "ab*a");
+    same(union(&[load("ab"), load("c+")]), "ab|c+");
+    same(star(&[load("ab")]), "(ab)*");
+    same(star(&[load("ab"), load("c")]), "(ab|c)*");
+}
+
+#[test]
+fn reverse_swaps_anchors() {
+    let nfa = |pattern| build_nfa(pattern, Options::default()).unwrap();
+    same(reverse(&nfa("^ab")), "ba$");
+    same(reverse(&nfa("ab$|c")), "^ba|c");
+    same(reverse(&nfa("(?m)^a\\nb$")), "(?m)^b\\na$");
+    same(reverse(&nfa("\\bab")), "ba\\b");
+}
@@ -0,0 +1,98 @@
+// the derivative route has to give the same minimal dfa as thompson + subset construction
+use prac_1::canon::isomorphic;
+use prac_1::dense::DenseDfa;
+use prac_1::deriv::{self, Re};
+use prac_1::min_dfa::min_dfa;
+use prac_1::{compile, Options};
+
+fn both(pattern: &str, options: Options) {
+    let thompson = compile(pattern, options).unwrap().min_dfa;
+    let derived = min_dfa(deriv::dfa(&deriv::re(pattern, options).unwrap()));
+    assert!(isomorphic(&thompson, &derived), "{}", pattern);
+}
+
+fn accepts(re: &Re, word: &str) -> bool {
+    DenseDfa::new(&deriv::dfa(re)).is_match(word)
+}
+
+#[test]
+fn agrees_with_thompson() {
+    for pattern in [
+        "a",
+        "ab|ac",
+        "(a|b)*abb",
+        "(a|b)*a(a|b)(a|b)",
+        "a+b?c*",
+        "(ab)*|(ba)*",
+        "[a-c]+x|b",
+        "((a|b)*c)+",
+        "(?i)ab",
+        "x(y|z)?x",
+    ] {
+        both(pattern, Options::default());
+    }
+}
+
+#[test]
+fn agrees_with_thompson_in_byte_mode() {
+    let bytes = Options {
+        bytes: true,
+        ..Options::default()
+    };
+    for pattern in ["é+", "[^a]b", "[a-zé]*", "(ü|u)e"] {
+        both(pattern, bytes);
+    }
+}
+
+#[test]
+fn rejects_what_nfa_rejects() {
+    assert!(deriv::re("a**", Options::default()).is_err());
+    assert!(deriv::re("a|", Options::default()).is_err());
+    assert!(deriv::re("^a", Options::default()).is_err());
+}
+
+#[test]
+fn intersection() {
+    let even_as = deriv::re("(b*ab*a)*b*", Options::default()).unwrap();
+    let ends_b = deriv::re("(a|b)*b", Options::default()).unwrap();
+    let both = Re::and(vec![even_as, ends_b]);
+    assert!(accepts(&both, "aab"));
+    assert!(accepts(&both, "b"));
+    assert!(!accepts(&both, "ab"));
+    assert!(!accepts(&both, "aa"));
+
+    let never = Re::and(vec![
+        deriv::re("a+", Options::default()).unwrap(),
+        deriv::re("b+", Options::default()).unwrap(),
+    ]);
+    // not Re::Empty by itself, but no derivative of it is nullable
+    assert!(!deriv::dfa(&never).node_weights().any(|accept| *accept));
+}
+
+#[test]
+fn complement() {
+    let not_abb = Re::complement(deriv::re("(a|b)*abb", Options::default()).unwrap());
+    assert!(accepts(&not_abb, ""));
+    assert!(accepts(&not_abb, "abba"));
+    assert!(!accepts(&not_abb, "babb"));
+    // only words over the symbols the regexes use
+    assert!(!accepts(&not_abb, "c"));
+
+    let twice = Re::complement(not_abb.clone());
+    let original = deriv::re("(a|b)*abb", Options::default()).unwrap();
+    assert_eq!(twice, original);
+}
+
+#[test]
+fn smart_constructors_normalize() {
+    let a = Re::set(vec![prac_1::Edge::Literal('a')]);
+    let b = Re::set(vec![prac_1::Edge::Literal('b')]);
+    assert_eq!(
+        Re::alt(vec![b.clone(), a.clone(), Re::Empty, a.clone()]),
+        Re::alt(vec![a.clone(), b.clone()])
+    );
+    assert_eq!(Re::concat(vec![Re::Epsilon, a.clone()]), a);
+    assert_eq!(Re::concat(vec![a.clone(), Re::Empty]), Re::Empty);
+    assert_eq!(Re::star(Re::star(a.clone())), Re::star(a.clone()));
+    assert_eq!(Re::and(vec![Re::universal(), a.clone()]), a);
+}
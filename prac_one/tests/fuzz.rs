@@ -0,0 +1,136 @@
+// property tests for the whole pipeline: random regexes in the supported subset are compiled
+// nfa -> dfa -> min dfa and every stage has to agree with the regex crate on random words,
+// proptest shrinks any failure down to a small regex and word
+use proptest::prelude::*;
+use regex::Regex;
+
+use prac_1::canon::isomorphic;
+use prac_1::dense::DenseDfa;
+use prac_1::deriv;
+use prac_1::dfa::dfa;
+use prac_1::min_dfa::min_dfa;
+use prac_1::nfa::{nfa, Options};
+use prac_1::sim::simulate;
+use prac_1::tdfa::tagged_dfa;
+use prac_1::Edge;
+
+#[derive(Debug, Clone)]
+enum Re {
+    Literal(char),
+    Class(&'static str),
+    Concat(Vec<Re>),
+    Alternation(Vec<Re>),
+    Star(Box<Re>),
+    Plus(Box<Re>),
+    Optional(Box<Re>),
+}
+
+impl Re {
+    fn render(&self) -> String {
+        match self {
+            Re::Literal(c) => c.to_string(),
+            Re::Class(class) => class.to_string(),
+            Re::Concat(items) => items.iter().map(|r| r.atom()).collect::<String>(),
+            Re::Alternation(alts) => alts
+                .iter()
+                .map(|r| r.render())
+                .collect::<Vec<String>>()
+                .join("|"),
+            Re::Star(r) => format!("{}*", r.atom()),
+            Re::Plus(r) => format!("{}+", r.atom()),
+            Re::Optional(r) => format!("{}?", r.atom()),
+        }
+    }
+
+    // anything that isn't a single symbol gets a group, so operators never stack up
+    fn atom(&self) -> String {
+        match self {
+            Re::Literal(_) | Re::Class(_) => self.render(),
+            _ => format!("({})", self.render()),
+        }
+    }
+}
+
+fn regex() -> impl Strategy<Value = Re> {
+    let leaf = prop_oneof![
+        4 => prop::sample::select(vec!['a', 'b', 'c']).prop_map(Re::Literal),
+        1 => prop::sample::select(vec!["[ab]", "[a-c]", "[^a]"]).prop_map(Re::Class),
+    ];
+    leaf.prop_recursive(4, 24, 3, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 2..4).prop_map(Re::Concat),
+            prop::collection::vec(inner.clone(), 2..4).prop_map(Re::Alternation),
+            inner.clone().prop_map(|r| Re::Star(Box::new(r))),
+            inner.clone().prop_map(|r| Re::Plus(Box::new(r))),
+            inner.prop_map(|r| Re::Optional(Box::new(r))),
+        ]
+    })
+}
+
+fn words() -> impl Strategy<Value = Vec<String>> {
+    prop::collection::vec("[abcd]{0,8}", 1..16)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(256))]
+
+    #[test]
+    fn pipeline_agrees_with_regex_crate(re in regex(), words in words()) {
+        let pattern = re.render();
+        let expected = Regex::new(&format!("^(?:{})$", pattern)).unwrap();
+
+        for bytes in [false, true] {
+            let nfa = nfa(pattern.clone(), Options { bytes, ..Options::default() }).unwrap();
+            let dfa = dfa(&nfa, 0);
+            let dfa_dense = DenseDfa::new(&dfa);
+            let min = min_dfa(dfa);
+            let min_dense = DenseDfa::new(&min);
+
+            for word in &words {
+                let want = expected.is_match(word);
+                if !bytes {
+                    prop_assert_eq!(simulate(&nfa, 0, word).0, want, "nfa: {} on {:?}", pattern, word);
+                }
+                prop_assert_eq!(dfa_dense.is_match(word), want, "dfa: {} on {:?}", pattern, word);
+                prop_assert_eq!(min_dense.is_match(word), want, "min dfa: {} on {:?}", pattern, word);
+            }
+        }
+    }
+
+    #[test]
+    fn derivatives_agree_with_thompson(re in regex()) {
+        let pattern = re.render();
+        for bytes in [false, true] {
+            let options = Options { bytes, ..Options::default() };
+            let thompson = min_dfa(dfa(&nfa(pattern.clone(), options).unwrap(), 0));
+            let derived = min_dfa(deriv::dfa(&deriv::re(&pattern, options).unwrap()));
+            prop_assert!(isomorphic(&thompson, &derived), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn captures_agree_with_regex_crate(re in regex(), words in words()) {
+        // every group the generator wraps around a subexpression is a capture group
+        let pattern = re.render();
+        let expected = Regex::new(&format!("^(?:{})$", pattern)).unwrap();
+        // the words are ascii, so byte offsets and char offsets are the same
+        for bytes in [false, true] {
+            let options = Options { bytes, tags: true, ..Options::default() };
+            let tdfa = tagged_dfa(&nfa(pattern.clone(), options).unwrap()).unwrap();
+
+            for word in &words {
+                let symbols = if bytes {
+                    word.bytes().map(Edge::Byte).collect::<Vec<Edge>>()
+                } else {
+                    word.chars().map(Edge::Literal).collect::<Vec<Edge>>()
+                };
+                let want = expected.captures(word).map(|caps| {
+                    caps.iter()
+                        .map(|m| m.map(|m| (m.start(), m.end())))
+                        .collect::<Vec<Option<(usize, usize)>>>()
+                });
+                prop_assert_eq!(tdfa.captures(&symbols), want, "{} on {:?}", pattern, word);
+            }
+        }
+    }
+}
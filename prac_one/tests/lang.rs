@@ -0,0 +1,46 @@
+// the words of a min dfa: the shortest one, all of them up to a length and how many per length
+use prac_1::lang::{count, enumerate, shortest, to_string};
+use prac_1::{compile, Dfa, Options};
+
+fn min(pattern: &str) -> Dfa {
+    compile(pattern, Options::default()).unwrap().min_dfa
+}
+
+fn words(pattern: &str, max_len: usize) -> Vec<String> {
+    enumerate(&min(pattern), max_len)
+        .iter()
+        .map(|w| to_string(w))
+        .collect()
+}
+
+#[test]
+fn shortest_word() {
+    let shortest = |pattern| shortest(&min(pattern)).map(|w| to_string(&w));
+    assert_eq!(shortest("(a|b)*abb").as_deref(), Some("abb"));
+    // the smallest of the shortest ones
+    assert_eq!(shortest("ba|c|b+").as_deref(), Some("b"));
+    assert_eq!(shortest("a*").as_deref(), Some(""));
+    // ^ after a symbol never holds, so nothing is accepted
+    assert_eq!(shortest("a^b"), None);
+}
+
+#[test]
+fn enumerates_shortest_first_then_sorted() {
+    assert_eq!(words("ab|b|a", 5), ["a", "b", "ab"]);
+    assert_eq!(words("a*", 3), ["", "a", "aa", "aaa"]);
+    assert_eq!(words("(a|b)c*", 2), ["a", "b", "ac", "bc"]);
+    assert_eq!(words("abc", 2), Vec::<String>::new());
+    assert_eq!(words("a^b", 5), Vec::<String>::new());
+}
+
+#[test]
+fn counts_per_length() {
+    assert_eq!(count(&min("(a|b)*"), 4), [1, 2, 4, 8, 16]);
+    assert_eq!(count(&min("[ab]c?"), 3), [0, 2, 2, 0]);
+    assert_eq!(count(&min("a^b"), 3), [0, 0, 0, 0]);
+    // far past what fits, the counts stop at u128::MAX instead of wrapping
+    let counts = count(&min("[a-p]*"), 40);
+    assert_eq!(counts[32], u128::MAX);
+    assert_eq!(counts[40], u128::MAX);
+    assert_eq!(counts[31], 16u128.pow(31));
+}
@@ -0,0 +1,279 @@
+// the library api as another crate sees it
+use std::time::Duration;
+
+use petgraph::graph::NodeIndex;
+use regex::Regex;
+
+use prac_1::dense::DenseDfa;
+use prac_1::dfa::{dfa_limited, dfa_traced, BlowUp, Limits};
+use prac_1::tdfa::tagged_dfa_limited;
+use prac_1::{build_nfa, compile, compile_limited, lang, to_dot, to_json, to_xml, Edge, Options};
+
+fn accepts(pattern: &str, word: &str) -> bool {
+    let compiled = compile(pattern, Options::default()).unwrap();
+    DenseDfa::new(&compiled.min_dfa).is_match(word)
+}
+
+#[test]
+fn compiles_every_stage() {
+    let compiled = compile("(a|b)*abb", Options::default()).unwrap();
+    assert!(compiled.nfa.node_count() > compiled.dfa.node_count());
+    assert_eq!(compiled.dfa.node_count(), 5);
+    assert_eq!(compiled.min_dfa.node_count(), 4);
+    assert!(compiled.trimmed.unreachable.is_empty());
+    assert!(compiled.trimmed.dead.is_empty());
+}
+
+#[test]
+fn matches_words() {
+    assert!(accepts("(a|b)*abb", "abb"));
+    assert!(accepts("(a|b)*abb", "babaabb"));
+    assert!(!accepts("(a|b)*abb", "abba"));
+    assert!(!accepts("(a|b)*abb", ""));
+    assert!(accepts("a+b?", "aaa"));
+    assert!(!accepts("a+b?", "b"));
+}
+
+#[test]
+fn options_change_the_language() {
+    let ignore_case = Options {
+        ignore_case: true,
+        ..Options::default()
+    };
+    let compiled = compile("ab", ignore_case).unwrap();
+    assert!(DenseDfa::new(&compiled.min_dfa).is_match("aB"));
+    assert!(!accepts("ab", "aB"));
+
+    let bytes = Options {
+        bytes: true,
+        ..Options::default()
+    };
+    let compiled = compile("é", bytes).unwrap();
+    assert!(compiled
+        .min_dfa
+        .edge_weights()
+        .all(|e| matches!(e, Edge::Byte(_))));
+    assert!(DenseDfa::new(&compiled.min_dfa).is_match("é"));
+
+    // . is one range of chars, or every utf-8 sequence over bytes
+    for options in [Options::default(), bytes] {
+        let compiled = compile("(?s)a.b", options).unwrap();
+        assert!(DenseDfa::new(&compiled.min_dfa).is_match("a\nb"));
+        assert!(DenseDfa::new(&compiled.min_dfa).is_match("aéb"));
+        assert!(!DenseDfa::new(&compile("a.b", options).unwrap().min_dfa).is_match("a\nb"));
+    }
+}
+
+#[test]
+fn char_classes_are_ranges() {
+    // the classes cover most of unicode, but only take an edge per range
+    for pattern in ["\\d+", "\\w+", "[^a]+", "a.b", "(?s)a.b", "[a-z]+[^a-z]"] {
+        let compiled = compile(pattern, Options::default()).unwrap();
+        let dense = DenseDfa::new(&compiled.min_dfa);
+        let expected = Regex::new(&format!("^(?:{})$", pattern)).unwrap();
+        for word in ["", "a", "7", "٣", "é", "abc", "a\nb", "aéb", "zz9", "b_é"] {
+            assert_eq!(
+                dense.is_match(word),
+                expected.is_match(word),
+                "{} on {:?}",
+                pattern,
+                word
+            );
+        }
+    }
+
+    // one transition per class, labelled with the range of the class
+    let min_dfa = compile("[a-z]+", Options::default()).unwrap().min_dfa;
+    assert_eq!(min_dfa.node_count(), 2);
+    assert!(min_dfa.edge_weights().all(|e| *e == Edge::Range('a', 'z')));
+    assert_eq!(min_dfa.edge_count(), 2);
+    let stats = compile("\\w", Options::default()).unwrap().stats();
+    assert!(stats.symbols > 100_000);
+    assert!(stats.classes < 1_000);
+}
+
+#[test]
+fn one_transition_per_class() {
+    let bytes = Options {
+        bytes: true,
+        ..Options::default()
+    };
+    let compiled = compile("[a-z]+", bytes).unwrap();
+    for dfa in [&compiled.dfa, &compiled.min_dfa] {
+        assert!(dfa
+            .edge_weights()
+            .all(|e| *e == Edge::ByteRange(b'a', b'z')));
+        assert!(dfa.node_indices().all(|n| dfa.edges(n).count() <= 1));
+    }
+
+    // m cuts [a-z] into three classes
+    let dfa = compile("[a-z]0|m", bytes).unwrap().dfa;
+    let mut labels = dfa
+        .edges(NodeIndex::new(0))
+        .map(|e| *e.weight())
+        .collect::<Vec<Edge>>();
+    labels.sort_by_key(|e| e.bounds());
+    assert_eq!(
+        labels,
+        [
+            Edge::ByteRange(b'a', b'l'),
+            Edge::Byte(b'm'),
+            Edge::ByteRange(b'n', b'z')
+        ]
+    );
+}
+
+#[test]
+fn rejects_invalid_patterns() {
+    assert!(compile("a**", Options::default()).is_err());
+    assert!(compile("a|", Options::default()).is_err());
+    assert!(compile("(a", Options::default()).is_err());
+}
+
+#[test]
+fn equivalent_patterns_have_no_distinguishing_word() {
+    let first = compile("(a|b)*", Options::default()).unwrap().min_dfa;
+    let second = compile("(a*b*)*", Options::default()).unwrap().min_dfa;
+    assert_eq!(lang::distinguish(&first, &second), None);
+
+    let third = compile("(a|b)+", Options::default()).unwrap().min_dfa;
+    assert_eq!(lang::distinguish(&first, &third), Some(Vec::new()));
+}
+
+#[test]
+fn exporters_cover_every_state() {
+    let min_dfa = compile("ab*", Options::default()).unwrap().min_dfa;
+    let xml = to_xml(&min_dfa, "mindfa");
+    assert!(xml.starts_with("<mindfa>"));
+    assert!(xml.trim_end().ends_with("</mindfa>"));
+    for n in 0..min_dfa.node_count() {
+        assert!(xml.contains(&format!("<{}>", n)));
+    }
+    assert!(to_json(&min_dfa).contains("\"b\""));
+    assert!(to_dot(&min_dfa).starts_with("digraph"));
+}
+
+#[test]
+fn dense_dfa_round_trips_through_bytes() {
+    let min_dfa = compile("[a-c]+x|b", Options::default()).unwrap().min_dfa;
+    let dense = DenseDfa::new(&min_dfa);
+    let loaded = DenseDfa::from_bytes(dense.as_bytes()).unwrap();
+    for word in ["ax", "bcx", "b", "x", "bb", ""] {
+        assert_eq!(loaded.is_match(word), dense.is_match(word), "{:?}", word);
+    }
+    assert!(DenseDfa::from_bytes(&dense.as_bytes()[..8]).is_err());
+}
+
+#[test]
+fn dense_dfa_rejects_hostile_headers() {
+    // u32::MAX states and classes would overflow the length check if it wasn't checked
+    let mut header = b"DDFA".to_vec();
+    for field in [1u32, 0, u32::MAX, u32::MAX, 0, u32::MAX] {
+        header.extend_from_slice(&field.to_le_bytes());
+    }
+    assert_eq!(
+        DenseDfa::from_bytes(&header).unwrap_err(),
+        "Dense dfa has the wrong length"
+    );
+
+    // swapping the first two ranges leaves them out of order for the binary search
+    let min_dfa = compile("[a-c]+x|b", Options::default()).unwrap().min_dfa;
+    let mut bytes = DenseDfa::new(&min_dfa).as_bytes().to_vec();
+    let (first, second) = bytes[28..52].split_at_mut(12);
+    first.swap_with_slice(second);
+    assert_eq!(
+        DenseDfa::from_bytes(&bytes).unwrap_err(),
+        "Dense dfa ranges are out of order"
+    );
+}
+
+#[test]
+fn stats_per_stage() {
+    let stats = compile("(a|b)*abb", Options::default()).unwrap().stats();
+    assert_eq!(stats.symbols, 2);
+    assert_eq!(stats.classes, 2);
+    assert_eq!(stats.min_states, 4);
+    assert!(stats.dfa_states >= stats.min_states);
+    assert!(stats.nfa_nodes > stats.dfa_states);
+    assert!(stats.nfa_edges >= stats.nfa_nodes - 1);
+}
+
+#[test]
+fn limits_stop_the_blow_up() {
+    // the nth symbol from the end being an a needs 2^n dfa states
+    let pattern = "(a|b)*a(a|b)(a|b)(a|b)(a|b)(a|b)";
+    let nfa = build_nfa(pattern, Options::default()).unwrap();
+    let limits = Limits {
+        max_states: Some(16),
+        time_budget: None,
+    };
+    match dfa_limited(&nfa, 0, limits) {
+        Err(BlowUp::States {
+            limit, nfa_states, ..
+        }) => {
+            assert_eq!(limit, 16);
+            assert_eq!(nfa_states, nfa.node_count());
+        }
+        other => panic!("expected a state blow-up, got {:?}", other),
+    }
+    let limits = Limits {
+        max_states: None,
+        time_budget: Some(Duration::ZERO),
+    };
+    assert!(matches!(
+        dfa_limited(&nfa, 0, limits),
+        Err(BlowUp::Time { .. })
+    ));
+    let err = compile_limited(pattern, Options::default(), limits).unwrap_err();
+    assert!(err.contains("budget"), "{}", err);
+
+    // exactly enough room (2^6 states plus the start) is the same as no limit
+    let limits = Limits {
+        max_states: Some(65),
+        time_budget: Some(Duration::from_secs(60)),
+    };
+    let limited = compile_limited(pattern, Options::default(), limits).unwrap();
+    assert_eq!(
+        limited.dfa.node_count(),
+        compile(pattern, Options::default())
+            .unwrap()
+            .dfa
+            .node_count()
+    );
+}
+
+#[test]
+fn traced_and_tagged_dfas_keep_to_the_limits() {
+    let pattern = "(a|b)*a(a|b)(a|b)(a|b)(a|b)(a|b)";
+    let states = Limits {
+        max_states: Some(16),
+        time_budget: None,
+    };
+    let time = Limits {
+        max_states: None,
+        time_budget: Some(Duration::ZERO),
+    };
+
+    let nfa = build_nfa(pattern, Options::default()).unwrap();
+    assert!(matches!(
+        dfa_traced(&nfa, 0, states),
+        Err(BlowUp::States { limit: 16, .. })
+    ));
+    assert!(matches!(
+        dfa_traced(&nfa, 0, time),
+        Err(BlowUp::Time { .. })
+    ));
+    let (dfa, steps) = dfa_traced(&nfa, 0, Limits::default()).unwrap();
+    assert_eq!(steps.len(), dfa.node_count());
+
+    let tags = Options {
+        tags: true,
+        ..Options::default()
+    };
+    let nfa = build_nfa(pattern, tags).unwrap();
+    let err = tagged_dfa_limited(&nfa, states).unwrap_err();
+    assert!(err.contains("more than 16 DFA states"), "{}", err);
+    let err = tagged_dfa_limited(&nfa, time).unwrap_err();
+    assert!(err.contains("budget"), "{}", err);
+    assert!(tagged_dfa_limited(&nfa, Limits::default()).is_ok());
+}
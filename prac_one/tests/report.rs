@@ -0,0 +1,79 @@
+// the trace report, the tables and how markdown and html write them out
+use prac_1::dfa::{dfa_traced, Limits};
+use prac_1::min_dfa::min_dfa_history;
+use prac_1::report::{tables, to_html, to_markdown, Table};
+use prac_1::{build_nfa, Options};
+
+fn report(pattern: &str) -> Vec<Table> {
+    let nfa = build_nfa(pattern, Options::default()).unwrap();
+    let (dfa, steps) = dfa_traced(&nfa, 0, Limits::default()).unwrap();
+    let (min_dfa, history) = min_dfa_history(dfa);
+    tables(&steps, &history, &min_dfa)
+}
+
+#[test]
+fn markdown_tables() {
+    let markdown = to_markdown("a|b", &report("a|b"));
+    assert_eq!(
+        markdown,
+        "# a|b
+
+## Subset construction
+
+| DFA state | NFA states | symbols | move | ε-closure | → |
+|---|---|---|---|---|---|
+| 0 | {0, 2, 4} | a | {3} | {1, 3} | 1 |
+|  |  | b | {5} | {1, 5} | 2 |
+| 1 (accept) | {1, 3} | a | {} | {} | - |
+|  |  | b | {} | {} | - |
+| 2 (accept) | {1, 5} | a | {} | {} | - |
+|  |  | b | {} | {} | - |
+
+## Partition refinement
+
+| pass | groups of DFA states |
+|---|---|
+| 0 | {1, 2} (accept) {0} |
+| 1 | {0} {1, 2} (accept) |
+
+## Minimal DFA
+
+| state | accept | symbols | → |
+|---|---|---|---|
+| 0 | false | [a-b] | 1 |
+| 1 | true |  | - |
+"
+    );
+}
+
+#[test]
+fn every_stage_gets_a_table() {
+    let tables = report("(a|b)*abb");
+    let titles = tables
+        .iter()
+        .map(|t| t.title.as_str())
+        .collect::<Vec<&str>>();
+    assert_eq!(
+        titles,
+        ["Subset construction", "Partition refinement", "Minimal DFA"]
+    );
+    for table in &tables {
+        assert!(table.rows.iter().all(|r| r.len() == table.header.len()));
+    }
+    // a row per dfa state and symbol class, and one per min dfa state and target
+    assert_eq!(tables[0].rows.len(), 5 * 2);
+    assert_eq!(tables[2].rows.len(), 4 * 2);
+}
+
+#[test]
+fn cells_are_escaped() {
+    let markdown = to_markdown("pipes", &report("\\||a"));
+    assert!(markdown.contains("| \\| |"), "{}", markdown);
+
+    let html = to_html("<&>", &report("<|&"));
+    assert!(html.contains("<title>&lt;&amp;&gt;</title>"), "{}", html);
+    assert!(html.contains("<td>&lt;</td>"), "{}", html);
+    assert!(html.contains("<td>&amp;</td>"), "{}", html);
+    assert_eq!(html.matches("<table>").count(), 3);
+    assert!(html.trim_end().ends_with("</html>"));
+}
@@ -0,0 +1,56 @@
+// the simplified regex has to print as expected and give a smaller nfa, fuzz.rs checks it still
+// matches the same words and captures the same groups
+use prac_1::canon::isomorphic;
+use prac_1::nfa::parse;
+use prac_1::simplify::simplify;
+use prac_1::{build_nfa, compile, Options};
+
+fn simplified(pattern: &str, options: Options) -> String {
+    simplify(&parse(pattern, options).unwrap(), options).to_string()
+}
+
+#[test]
+fn prints_the_simplified_pattern() {
+    let options = Options::default();
+    for (pattern, expected) in [
+        ("ab|ac", "a(?:b|c)"),
+        ("abc|abd|ae", "a(?:b(?:c|d)|e)"),
+        ("(a*)*", "a*"),
+        ("((a+)?)", "a*"),
+        ("(?:(?:ab)c)d", "abcd"),
+        ("(a|(b|c))|a", "a|b|c"),
+        ("ab|a", "ab?"),
+        ("if|int|in", "i(?:f|nt?)"),
+        ("(a|b)*abb", "(?:a|b)*abb"),
+    ] {
+        assert_eq!(simplified(pattern, options), expected, "{}", pattern);
+    }
+}
+
+#[test]
+fn keeps_captures_and_priorities_with_tags() {
+    let tags = Options {
+        tags: true,
+        ..Options::default()
+    };
+    // different groups are never the same prefix, and a lazy repetition stays apart from a greedy one
+    assert_eq!(simplified("(a)b|(a)c", tags), "(a)b|(a)c");
+    assert_eq!(simplified("(?:a*?)*", tags), "(?:a*?)*");
+    assert_eq!(simplified("(?:a*)*", tags), "a*");
+    // the alternative that is only the prefix is tried where it was
+    assert_eq!(simplified("a|ab", tags), "ab??");
+    assert_eq!(simplified("ab|a|ac", tags), "ab|a|ac");
+}
+
+#[test]
+fn smaller_nfa_same_min_dfa() {
+    let options = Options::default();
+    let nfa = build_nfa("abc|abd|abe", options).unwrap();
+    let unfactored = build_nfa("abc|xbd|ybe", options).unwrap();
+    assert!(nfa.node_count() < unfactored.node_count());
+    let min_dfa = compile("abc|abd|abe", options).unwrap().min_dfa;
+    assert!(isomorphic(
+        &min_dfa,
+        &compile("ab[c-e]", options).unwrap().min_dfa
+    ));
+}
@@ -0,0 +1,45 @@
+// out.xml for a regex has to stay the same from run to run, the expected files are in snapshots/
+use prac_1::canon::{canonical, isomorphic};
+use prac_1::{compile, to_xml, Options};
+
+fn xml(pattern: &str) -> String {
+    to_xml(
+        &compile(pattern, Options::default()).unwrap().min_dfa,
+        "mindfa",
+    )
+}
+
+#[test]
+fn matches_snapshots() {
+    for (pattern, expected) in [
+        ("(a|b)*abb", include_str!("snapshots/abb.xml")),
+        ("a+b?c*", include_str!("snapshots/abc.xml")),
+        ("[a-c]+x|b", include_str!("snapshots/class.xml")),
+    ] {
+        assert_eq!(xml(pattern), expected, "{}", pattern);
+    }
+}
+
+#[test]
+fn output_is_deterministic() {
+    // the dfa before minimizing is numbered in hash map order, so every run gets a new shuffle
+    for pattern in ["(a|b)*a(a|b)(a|b)", "(ab|ba)*c+", "x(y|z)?x|yy"] {
+        let first = xml(pattern);
+        for _ in 0..8 {
+            assert_eq!(xml(pattern), first, "{}", pattern);
+        }
+    }
+}
+
+#[test]
+fn canonical_form() {
+    let compiled = compile("(a|b)*abb", Options::default()).unwrap();
+    let canon = canonical(&compiled.dfa);
+    assert!(isomorphic(&compiled.dfa, &canon));
+    assert_eq!(canonical(&canon).edge_count(), canon.edge_count());
+    assert!(isomorphic(&compiled.min_dfa, &canonical(&compiled.min_dfa)));
+    // same language, but not the same automaton
+    assert!(!isomorphic(&compiled.dfa, &compiled.min_dfa));
+    let other = compile("(a|b)*bba", Options::default()).unwrap().min_dfa;
+    assert!(!isomorphic(&compiled.min_dfa, &other));
+}
@@ -0,0 +1,54 @@
+// rewriting with the longest match of any rule, and minimization keeping apart states that write
+// different things
+use prac_1::transducer::{parse_rules, Rule, Transducer};
+use prac_1::{compile, Options};
+
+fn transducer(rules: &str) -> Transducer {
+    Transducer::new(&parse_rules(rules).unwrap(), Options::default()).unwrap()
+}
+
+#[test]
+fn minimization_respects_outputs() {
+    // a|b needs just the start and one accept, the rules need an accept per output
+    assert_eq!(
+        compile("a|b", Options::default())
+            .unwrap()
+            .min_dfa
+            .node_count(),
+        2
+    );
+    let t = transducer("a => X\nb => Y");
+    assert_eq!(t.dfa.node_count(), 3);
+    // but two rules writing the same thing still can't be merged, they are different rules
+    let t = transducer("a => X\nb => X");
+    assert_eq!(t.dfa.node_count(), 3);
+    let t = transducer("a|b => X");
+    assert_eq!(t.dfa.node_count(), 2);
+    assert_eq!(t.dfa[t.dfa.node_indices().nth(1).unwrap()], Rule(Some(0)));
+}
+
+#[test]
+fn rewrite_longest_match() {
+    let t = transducer("# numbers and names\n[0-9]+ => NUM\n[a-z]+ => ID\n\nif => IF");
+    assert_eq!(t.rewrite("if x1 = 42;"), "ID IDNUM = NUM;");
+    // the earlier rule wins when two match the same length
+    let t = transducer("if => IF\n[a-z]+ => ID");
+    assert_eq!(t.rewrite("if iff"), "IF ID");
+    // an empty output deletes the match
+    let t = transducer(" + =>");
+    assert_eq!(t.rewrite("a   b"), "ab");
+}
+
+#[test]
+fn translate_moore() {
+    let t = transducer("a => 1\nab => 2");
+    assert_eq!(t.translate("ab").as_deref(), Some("12"));
+    assert_eq!(t.translate("").as_deref(), Some(""));
+    assert_eq!(t.translate("b"), None);
+}
+
+#[test]
+fn bad_rules() {
+    assert!(parse_rules("no arrow").is_err());
+    assert!(Transducer::new(&parse_rules("a** => x").unwrap(), Options::default()).is_err());
+}
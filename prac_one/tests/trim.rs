@@ -0,0 +1,50 @@
+// trimming drops the unreachable and dead states and says which ones those were
+use prac_1::trim::trim;
+use prac_1::{compile, Dfa, Edge, Options};
+
+#[test]
+fn reports_what_was_dropped() {
+    // 0 -a-> 1 (accept) -b-> 4 -b-> 4, 2 -a-> 1 can't be reached and 3 can't reach an accept
+    let mut dfa = Dfa::new();
+    let states = [false, true, false, false, false].map(|accept| dfa.add_node(accept));
+    dfa.add_edge(states[0], states[1], Edge::Literal('a'));
+    dfa.add_edge(states[2], states[1], Edge::Literal('a'));
+    dfa.add_edge(states[0], states[3], Edge::Literal('b'));
+    dfa.add_edge(states[1], states[4], Edge::Literal('b'));
+    dfa.add_edge(states[4], states[4], Edge::Literal('b'));
+
+    let (trimmed, dropped) = trim(&dfa);
+    assert_eq!(dropped.unreachable, [2]);
+    assert_eq!(dropped.dead, [3, 4]);
+    assert_eq!(trimmed.node_count(), 2);
+    assert_eq!(trimmed.edge_count(), 1);
+    assert!(!trimmed[trimmed.node_indices().next().unwrap()]);
+}
+
+#[test]
+fn keeps_the_start_of_an_empty_language() {
+    let mut dfa = Dfa::new();
+    let start = dfa.add_node(false);
+    let other = dfa.add_node(false);
+    dfa.add_edge(start, other, Edge::Literal('a'));
+
+    let (trimmed, dropped) = trim(&dfa);
+    assert!(dropped.unreachable.is_empty());
+    // the start is dead too but stays
+    assert_eq!(dropped.dead, [1]);
+    assert_eq!(trimmed.node_count(), 1);
+    assert_eq!(trimmed.edge_count(), 0);
+}
+
+#[test]
+fn compile_trims_before_minimizing() {
+    let compiled = compile("(a|b)*abb", Options::default()).unwrap();
+    assert!(compiled.trimmed.unreachable.is_empty());
+    assert!(compiled.trimmed.dead.is_empty());
+
+    // after the a, ^ can never hold, so whatever the dfa reads next leads nowhere
+    let compiled = compile("a^b|c", Options::default()).unwrap();
+    assert!(compiled.trimmed.unreachable.is_empty());
+    assert!(!compiled.trimmed.dead.is_empty());
+    assert_eq!(compiled.min_dfa.node_count(), 2);
+}
@@ -17,8 +17,7 @@ impl AST {
     }
 
     pub fn prune(&mut self) {
-        let mut indices = self.ast.node_indices();
-        while let Some(node) = indices.next() {
+        for node in self.ast.node_indices() {
             if let Some(treenode) = self.ast.node_weight(node) {
                 let leaf = treenode.is_leaf;
                 let child_count = self.ast.edges(node).collect::<Vec<_>>().len();
@@ -36,10 +35,10 @@ impl AST {
         if let Some(root) = self.root() {
             let node = self.ast.node_weight(root.into()).unwrap();
             let start = format!("<{} id=\"{}\">\n", node.val, root);
-            file.write(start.as_bytes()).unwrap();
+            file.write_all(start.as_bytes()).unwrap();
             self.expand_children(root, &mut file, tab_index + 1);
             let end = format!("</{}>\n", node.val);
-            file.write(end.as_bytes()).unwrap();
+            file.write_all(end.as_bytes()).unwrap();
         }
     }
 
@@ -64,7 +63,7 @@ impl AST {
         if children.len() == 1 && self.ast.node_weight(children[0].into()).unwrap().is_leaf {
             let leaf = self.ast.node_weight(children[0].into()).unwrap();
             let start = format!("{}{}\n", tabs, leaf.val);
-            file.write(start.as_bytes()).unwrap();
+            file.write_all(start.as_bytes()).unwrap();
         } else {
             for child in children {
                 let node = self.ast.node_weight(child.into()).unwrap();
@@ -72,10 +71,10 @@ impl AST {
                     self.expand_children(child.index() as u32, file, tab_index + 1);
                 } else {
                     let start = format!("{}<{} id=\"{}\">\n", tabs, node.val, child);
-                    file.write(start.as_bytes()).unwrap();
+                    file.write_all(start.as_bytes()).unwrap();
                     self.expand_children(child.index() as u32, file, tab_index + 1);
                     let end = format!("{}</{}>\n", tabs, node.val);
-                    file.write(end.as_bytes()).unwrap();
+                    file.write_all(end.as_bytes()).unwrap();
                 }
             }
         }
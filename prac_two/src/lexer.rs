@@ -1,163 +1,307 @@
-use crate::token::{LexType, Token};
+use crate::token::{LexError, LexErrorKind, LexType, Span, Token};
 
 pub struct Lexer {
     source: Vec<char>,
+    offsets: Vec<usize>, // byte offset of every char, and of the end of the source
     position: usize,
-    line_offset: (usize, usize),
+    line: usize,
+    line_start: usize, // char the current line starts at
+    ended: bool,       // the $ token was given out
 }
 
+// where a token starts: char index, line and column
+type Mark = (usize, usize, usize);
+
+// the chars SPL has that aren't letters, digits, quotes, stars or the - of a number,
+// : only ever starts :=
+const PUNCTUATION: [char; 11] = [',', ';', '{', '}', '(', ')', ':', '^', '!', '<', '>'];
+
 impl Lexer {
     pub fn new(source: &str) -> Self {
+        let mut offsets = source
+            .char_indices()
+            .map(|(i, _)| i)
+            .collect::<Vec<usize>>();
+        offsets.push(source.len());
         Self {
             source: source.chars().collect(),
+            offsets,
             position: 0,
-            line_offset: (0, 0),
+            line: 1,
+            line_start: 0,
+            ended: false,
         }
     }
 
-    pub fn consume_enclosed(&mut self, is_comment: bool) -> String {
-        let start = self.position;
-        self.position += 1;
-        match is_comment {
-            true => {
-                while self.position < self.source.len() && self.source[self.position] != '*' {
-                    self.position += 1;
-                }
-            }
-            false => {
-                while self.position < self.source.len() && self.source[self.position] != '"' {
-                    self.position += 1;
-                }
+    fn peek(&self) -> Option<char> {
+        self.source.get(self.position).copied()
+    }
+
+    // steps over one char, \n, \r\n and a \r on its own all end a line
+    fn advance(&mut self) {
+        if let Some(c) = self.peek() {
+            self.position += 1;
+            if c == '\n' || (c == '\r' && self.peek() != Some('\n')) {
+                self.line += 1;
+                self.line_start = self.position;
             }
         }
-        self.position += 1;
+    }
+
+    fn mark(&self) -> Mark {
+        (
+            self.position,
+            self.line,
+            self.position - self.line_start + 1,
+        )
+    }
+
+    // from the mark up to where the lexer is now
+    fn span(&self, (start, line, column): Mark) -> Span {
+        Span {
+            start: self.offsets[start],
+            end: self.offsets[self.position],
+            line,
+            column,
+        }
+    }
+
+    fn lexeme(&self, (start, _, _): Mark) -> String {
         String::from_iter(&self.source[start..self.position])
     }
 
+    // the lexeme up to and including the closing quote or star, Err with the rest of the file if
+    // it never gets closed
+    pub fn consume_enclosed(&mut self, is_comment: bool) -> Result<String, String> {
+        let start = self.mark();
+        let close = if is_comment { '*' } else { '"' };
+        self.advance();
+        while self.peek().is_some_and(|c| c != close) {
+            self.advance();
+        }
+        if self.peek().is_none() {
+            return Err(self.lexeme(start));
+        }
+        self.advance();
+        Ok(self.lexeme(start))
+    }
+
     pub fn consume_numeric(&mut self) -> String {
-        let start = self.position;
-        if self.source[self.position] == '-' {
-            self.position += 1;
+        let start = self.mark();
+        if self.peek() == Some('-') {
+            self.advance();
         }
-        while self.position < self.source.len()
-            && (self.source[self.position].is_numeric() || self.source[self.position] == '.')
-        {
-            if self.source[self.position] == '\n' {
-                println!("AHHHH")
-            }
-            self.position += 1;
+        while self.peek().is_some_and(|c| c.is_numeric() || c == '.') {
+            self.advance();
         }
-        String::from_iter(&self.source[start..self.position])
+        self.lexeme(start)
     }
 
     pub fn consume_alphanumeric(&mut self) -> String {
-        let start = self.position;
-        while self.position < self.source.len() && self.source[self.position].is_alphanumeric() {
-            if self.source[self.position] == '\n' {
-                println!("AHHHH")
-            }
-            self.position += 1;
+        let start = self.mark();
+        while self.peek().is_some_and(|c| c.is_alphanumeric()) {
+            self.advance();
         }
-        String::from_iter(&self.source[start..self.position])
+        self.lexeme(start)
     }
 
+    // := is the only punctuation longer than one char
     pub fn consume_punctuation(&mut self) -> String {
-        let start = self.position;
-        match self.source[start] {
-            ':' => {
-                self.position += 2;
-                String::from_iter(&self.source[start..self.position])
-            }
-            _ => {
-                self.position += 1;
-                String::from_iter(&self.source[start..self.position])
-            }
+        let start = self.mark();
+        let first = self.peek();
+        self.advance();
+        if first == Some(':') && self.peek() == Some('=') {
+            self.advance();
         }
+        self.lexeme(start)
     }
 }
 
 impl Iterator for Lexer {
-    type Item = Token;
+    type Item = Result<Token, LexError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        while self.position < self.source.len() && self.source[self.position].is_whitespace() {
-            self.position += 1;
-            if self.source[self.position] == '\n'
-                || self.source[self.position] == '\r'
-                || self.source[self.position] == '\r' && self.source[self.position + 1] == '\n'
-            {
-                self.line_offset.0 += 1;
-                self.line_offset.1 = self.position;
-            }
+        while self.peek().is_some_and(|c| c.is_whitespace()) {
+            self.advance();
         }
 
-        if self.position == self.source.len() {
-            self.position += 1;
-            return Token::new(
-                String::from("$"),
-                LexType::End,
-                (self.line_offset.0, self.position - self.line_offset.1),
-            );
-        }
-
-        if self.position >= self.source.len() {
-            return None;
-        }
+        let start = self.mark();
+        let c = match self.peek() {
+            Some(c) => c,
+            None if self.ended => return None,
+            None => {
+                self.ended = true;
+                return Some(Token::new(
+                    String::from("$"),
+                    LexType::End,
+                    self.span(start),
+                ));
+            }
+        };
 
-        if self.source[self.position] == '"' || self.source[self.position] == '*' {
-            return match self.source[self.position] {
-                '"' => {
-                    let lexeme = self.consume_enclosed(false);
-                    // println!("stringy lex - {} // pos - {}", lexeme, self.position);
-                    Token::new(
-                        lexeme,
-                        LexType::ShortString,
-                        (self.line_offset.0, self.position - self.line_offset.1),
-                    )
-                }
-                '*' => {
-                    let lexeme = self.consume_enclosed(true);
-                    // println!("commenty lex - {} // pos - {}", lexeme, self.position);
-                    Token::new(
-                        lexeme,
-                        LexType::Comment,
-                        (self.line_offset.0, self.position - self.line_offset.1),
-                    )
-                }
-                _ => panic!("how did we get here? ('shortstring or comment' match)"),
-            };
+        if c == '"' || c == '*' {
+            let is_comment = c == '*';
+            let lexeme = self.consume_enclosed(is_comment);
+            return Some(match (lexeme, is_comment) {
+                (Ok(lexeme), true) => Token::new(lexeme, LexType::Comment, self.span(start)),
+                (Ok(lexeme), false) => Token::new(lexeme, LexType::ShortString, self.span(start)),
+                (Err(lexeme), is_comment) => Err(LexError {
+                    kind: if is_comment {
+                        LexErrorKind::UnterminatedComment
+                    } else {
+                        LexErrorKind::UnterminatedString
+                    },
+                    lexeme,
+                    span: self.span(start),
+                }),
+            });
         }
 
-        if self.source[self.position].is_numeric() || self.source[self.position] == '-' {
+        if c.is_numeric() || c == '-' {
             let lexeme = self.consume_numeric();
-            // println!("numy lex - {} // pos - {}", lexeme, self.position);
-            return Token::new(
-                lexeme,
-                LexType::Num,
-                (self.line_offset.0, self.position - self.line_offset.1),
-            );
+            return Some(Token::new(lexeme, LexType::Num, self.span(start)));
         }
 
-        if self.source[self.position].is_alphanumeric() {
+        if c.is_alphanumeric() {
             let lexeme = self.consume_alphanumeric();
-            // println!("alpha lex - {} // pos - {}", lexeme, self.position);
-            return Token::new(
-                lexeme,
-                LexType::Alpha,
-                (self.line_offset.0, self.position - self.line_offset.1),
-            );
+            return Some(Token::new(lexeme, LexType::Alpha, self.span(start)));
         }
 
-        if self.source[self.position].is_ascii_punctuation() {
+        if PUNCTUATION.contains(&c) {
             let lexeme = self.consume_punctuation();
-            // println!("puncty lex - {} // pos - {}", lexeme, self.position);
-            return Token::new(
-                lexeme,
-                LexType::Punct,
-                (self.line_offset.0, self.position - self.line_offset.1),
-            );
+            return Some(Token::new(lexeme, LexType::Punct, self.span(start)));
+        }
+
+        // anything else can't start a token, skip it and carry on with the next one
+        self.advance();
+        Some(Err(LexError {
+            kind: LexErrorKind::UnknownChar,
+            lexeme: c.to_string(),
+            span: self.span(start),
+        }))
+    }
+}
+
+// every token of the source, or every lexical error in it if there are any
+pub fn tokenize(source: &str) -> Result<Vec<Token>, Vec<LexError>> {
+    let mut tokens = Vec::new();
+    let mut errors = Vec::new();
+    for result in Lexer::new(source) {
+        match result {
+            Ok(token) => tokens.push(token),
+            Err(error) => errors.push(error),
         }
+    }
+    if errors.is_empty() {
+        Ok(tokens)
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::TokenType;
+
+    #[test]
+    fn tokenize_collects_every_error() {
+        let source = "n1:=@;\nn2:=1.5;\n\"short\";\nx9:=n1";
+        let errors = tokenize(source).unwrap_err();
+        let found = errors
+            .iter()
+            .map(|e| (e.kind.clone(), e.lexeme.as_str(), e.span.line))
+            .collect::<Vec<(LexErrorKind, &str, usize)>>();
+        assert_eq!(
+            found,
+            vec![
+                (LexErrorKind::UnknownChar, "@", 1),
+                (
+                    LexErrorKind::BadNumber("needs exactly two decimals"),
+                    "1.5",
+                    2
+                ),
+                (LexErrorKind::StringLength, "\"short\"", 3),
+                (LexErrorKind::UnknownWord, "x9", 4),
+            ]
+        );
+    }
+
+    // line and column of every token, the $ included
+    fn positions(source: &str) -> Vec<(usize, usize)> {
+        Lexer::new(source)
+            .map(|result| match result {
+                Ok(token) => token.span,
+                Err(error) => error.span,
+            })
+            .map(|span| (span.line, span.column))
+            .collect()
+    }
+
+    #[test]
+    fn crlf_is_one_line_break() {
+        assert_eq!(
+            positions("h;\r\nh;\r\n\r\nh"),
+            vec![(1, 1), (1, 2), (2, 1), (2, 2), (4, 1), (4, 2)]
+        );
+    }
+
+    #[test]
+    fn lone_cr_breaks_a_line_too() {
+        assert_eq!(
+            positions("h;\rh;\r\rh"),
+            vec![(1, 1), (1, 2), (2, 1), (2, 2), (4, 1), (4, 2)]
+        );
+        // and mixed with the others
+        assert_eq!(
+            positions("h\r\n\rh\n\r\nh"),
+            vec![(1, 1), (3, 1), (5, 1), (5, 2)]
+        );
+    }
+
+    #[test]
+    fn spans_are_bytes_and_columns_are_chars() {
+        let errors = tokenize("\"é\" h\n  @").unwrap_err();
+        assert_eq!(
+            errors[0].span,
+            Span {
+                start: 0,
+                end: 4,
+                line: 1,
+                column: 1
+            }
+        );
+        assert_eq!(
+            errors[1].span,
+            Span {
+                start: 9,
+                end: 10,
+                line: 2,
+                column: 3
+            }
+        );
+        let tokens = Lexer::new("\"é\" h")
+            .filter_map(Result::ok)
+            .collect::<Vec<Token>>();
+        assert_eq!(
+            tokens[0].span,
+            Span {
+                start: 5,
+                end: 6,
+                line: 1,
+                column: 5
+            }
+        );
+    }
 
-        None
+    #[test]
+    fn tokenize_ends_with_the_end_token() {
+        let tokens = tokenize("n1:=1.00;h").unwrap();
+        let lexemes = tokens
+            .iter()
+            .map(|t| t.lexeme.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(lexemes, vec!["n1", ":=", "1.00", ";", "h", "$"]);
+        assert_eq!(tokens.last().unwrap().token_type, TokenType::End);
     }
 }
@@ -4,12 +4,10 @@ pub mod parser;
 pub mod table;
 pub mod token;
 
-use std::{env, fs};
+use std::{env, fs, process};
 
 use ast::AST;
-use lexer::Lexer;
 use parser::Parser;
-use token::Token;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
@@ -28,13 +26,22 @@ fn main() {
         }
     };
 
-    if source_code.len() == 0 {
+    if source_code.is_empty() {
         println!("Empty source file - no parsing needed");
         return;
     }
     // println!("{}", source_code);
-    let lexer = Lexer::new(&source_code);
-    let tokens = lexer.collect::<Vec<Token>>();
+    let tokens = match lexer::tokenize(&source_code) {
+        Ok(tokens) => tokens,
+        Err(errors) => {
+            for error in &errors {
+                println!("\x1B[31m{}\x1B[0m", error);
+                println!("{}", error.span.underline(&source_code));
+            }
+            println!("{} lexical errors - no parsing done", errors.len());
+            process::exit(1);
+        }
+    };
 
     let mut parser = Parser::new(tokens);
     let tree = parser.parse();
@@ -1,13 +1,13 @@
 use crate::{table::Table, token::Token};
 use petgraph::{graph::NodeIndex, prelude::DiGraph};
 
-const TERMINALS: [&'static str; 47] = [
+const TERMINALS: [&str; 47] = [
     ",", "p", "{", "}", "0", "1", "2", "3", "4", "5", "6", "7", "8", "9", ";", "h", "c", ":=", "w",
     "(", ")", "i", "t", "e", "n", "b", "s", "a", "m", "d", "0.00", "-", ".", "T", "F", "^", "v",
     "!", "E", "<", ">", "\"", "*", "g", "o", "r", "$",
 ];
 
-const NON_TERMINAL: [&'static str; 32] = [
+const NON_TERMINAL: [&str; 32] = [
     "PROGR", "PROCDEFS", "PROC", "DIGITS", "D", "MORE", "ALGO", "SEQ", "INSTR", "CALL", "ASSIGN",
     "LOOP", "BRANCH", "ELSE", "NUMVAR", "BOOLVAR", "STRINGV", "NUMEXPR", "DECNUM", "NEG", "POS",
     "INT", "BOOLEXPR", "LOGIC", "CMPR", "STRI", "COMMENT", "C", "INPUT", "OUTPUT", "VALUE", "TEXT",
@@ -41,27 +41,19 @@ impl Parser {
 
         while !self.stack.is_empty() {
             let stack_top = &self.stack_top().val;
-            let term = self.tokens[0].lexeme.chars().next();
-            if let Some(term) = term {
-                let rule = if term == ':' {
-                    self.table
-                        .get_from_table(&stack_top, &self.tokens[0].lexeme)
-                        .to_owned()
-                } else if term == '0' && (stack_top == &"NUMEXPR" || stack_top == &"DECNUM") {
-                    self.table.get_from_table(&stack_top, "0.00").to_owned()
-                } else {
-                    self.table.get_from_table(&stack_top, &term.to_string())
-                };
+            let term = self.tokens[0].terminal();
+            if !term.is_empty() {
+                let rule = self.table.get_from_table(stack_top, &term);
 
                 // self._print_stack();
                 // println!("LEX ->{:?}", &self.tokens[0].lexeme);
-                
+
                 // println!("--------------------------");
-                if TERMINALS.contains(&stack_top.as_str()) || stack_top == &"C" {
+                if TERMINALS.contains(&stack_top.as_str()) || stack_top == "C" {
                     if self.match_stacktop() {
                         self.stack.pop();
                     } else {
-                        panic!("\x1B[31m{}\x1B[0m", "PARSE ERROR - FAILED TO MATCH STACKTOP")
+                        self.error("FAILED TO MATCH STACKTOP")
                     }
                 } else if let Some(rhs) = rule {
                     let term = self.stack.pop().unwrap();
@@ -69,17 +61,31 @@ impl Parser {
 
                     self.add_children(term, children.clone());
                     self.push_list(children);
-                } else if let None = rule {
-                    panic!("\x1B[31m{}\x1B[0m", "PARSE ERROR - NO TABLE RULE FOR INPUT")
+                } else if rule.is_none() {
+                    self.error("NO TABLE RULE FOR INPUT")
                 }
             } else {
-                panic!("\x1B[31m{}\x1B[0m", "PARSE ERROR - INPUT EMPTY BEFORE STACK")
+                self.error("INPUT EMPTY BEFORE STACK")
             }
         }
 
         self.ast.clone()
     }
 
+    // stops at the token the parser is on, with where it is in the source
+    fn error(&self, message: &str) -> ! {
+        match self.tokens.first() {
+            Some(token) => panic!(
+                "\x1B[31mPARSE ERROR - {} at {} (bytes {}..{}): {:?}\x1B[0m",
+                message, token.span, token.span.start, token.span.end, token.lexeme
+            ),
+            None => panic!(
+                "\x1B[31mPARSE ERROR - {} at the end of the input\x1B[0m",
+                message
+            ),
+        }
+    }
+
     fn init_parser(&mut self) {
         let first = "PROGR";
         let tokens = self.make_nodes(first);
@@ -110,7 +116,7 @@ impl Parser {
             let term = self.tokens[0].lexeme.chars().next().unwrap().to_string();
 
             if term.is_ascii() {
-                let leaf = tokens[0].lexeme.remove(0);
+                let leaf = tokens[0].take_first().unwrap();
                 self.tokens = tokens;
                 let leaf = self.ast.add_node(TreeNode {
                     val: leaf.to_string(),
@@ -121,6 +127,11 @@ impl Parser {
             }
         }
 
+        if tokens[0].terminal() != stack_top.val {
+            return false;
+        }
+
+        // the whole token, or just its first char when the rest of it is more terminals
         if tokens[0].lexeme.eq(&stack_top.val) {
             let leaf = tokens.remove(0);
             self.tokens = tokens;
@@ -132,18 +143,14 @@ impl Parser {
             return true;
         }
 
-        if tokens[0].lexeme.starts_with(&stack_top.val) {
-            let leaf = tokens[0].lexeme.remove(0);
-            self.tokens = tokens;
-            let leaf = self.ast.add_node(TreeNode {
-                val: leaf.to_string(),
-                is_leaf: true,
-            });
-            self.ast.add_edge(stack_top_index, leaf, ());
-            return true;
-        }
-
-        false
+        let leaf = tokens[0].take_first().unwrap();
+        self.tokens = tokens;
+        let leaf = self.ast.add_node(TreeNode {
+            val: leaf.to_string(),
+            is_leaf: true,
+        });
+        self.ast.add_edge(stack_top_index, leaf, ());
+        true
     }
 
     fn make_nodes(&mut self, production: &'static str) -> Vec<NodeIndex> {
@@ -4,6 +4,12 @@ pub struct Table {
     table: HashMap<(&'static str, &'static str), &'static str>,
 }
 
+impl Default for Table {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Table {
     pub fn new() -> Self {
         let mut table: HashMap<(&'static str, &'static str), &'static str> = HashMap::new();
@@ -1,3 +1,5 @@
+use std::fmt;
+
 #[derive(Debug, Clone)]
 pub enum LexType {
     End,
@@ -8,131 +10,501 @@ pub enum LexType {
     Punct,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TokenType {
     End,
-    Keyword,
-    Identifier,
-    AssignmentOperator,
-    LogicalOperator,
-    ComparisionOperator,
-    NumberLiteral,
+    // instructions
+    Halt,   // h
+    Call,   // c
+    While,  // w
+    If,     // i
+    Then,   // t
+    Else,   // e
+    Input,  // g
+    Output, // o
+    Return, // r
+    Proc,   // p
+    // number operators
+    Add,  // a
+    Mult, // m
+    Div,  // d
+    // boolean literals
+    True,  // T
+    False, // F
+    // variables are their type letter and digits, n1 is a number, b1 a boolean and s1 a string
+    NumVar,
+    BoolVar,
+    StringVar,
+    Identifier, // procedure name, p and digits
+    // the grammar spells out the digits of names and numbers one terminal per char, so once the
+    // parser took the first char off one of those tokens the rest of it is just digits (and the
+    // . of a number), this kind is only ever made by Token::take_first
+    Digits,
+    NumberLiteral(i64), // the value in cents, 1.50 is 150
     StringLiteral,
-    Punctuation,
+    AssignmentOperator, // :=
+    // logic and comparison operators
+    And,     // ^
+    Or,      // v
+    Not,     // !
+    Equal,   // E
+    Less,    // <
+    Greater, // >
+    // punctuation
+    Comma,      // ,
+    Semicolon,  // ;
+    OpenBrace,  // {
+    CloseBrace, // }
+    OpenParen,  // (
+    CloseParen, // )
     Comment,
 }
 
+// where a lexeme is in the source, start and end are byte offsets (end is one past the last
+// byte) and line and column (counted in chars) are where it starts, both from 1
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    // the line the span starts on, with ^ under the part of it the span covers
+    pub fn underline(&self, source: &str) -> String {
+        let line_start = source[..self.start]
+            .rfind(['\n', '\r'])
+            .map_or(0, |i| i + 1);
+        let line_end = source[self.start..]
+            .find(['\n', '\r'])
+            .map_or(source.len(), |i| self.start + i);
+        // tabs stay tabs so the ^ line up under them
+        let indent = source[line_start..self.start]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+        let width = source[self.start..self.end.clamp(self.start, line_end)]
+            .chars()
+            .count();
+        format!(
+            "{}\n{}{}",
+            &source[line_start..line_end],
+            indent,
+            "^".repeat(width.max(1))
+        )
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Token {
     pub token_type: TokenType,
     pub lexeme: String,
-    pub position: (usize, usize),
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LexErrorKind {
+    NonAsciiComment,
+    CommentLength,
+    UnterminatedComment,
+    NonAsciiString,
+    StringLength,
+    UnterminatedString,
+    UnknownWord,
+    UnknownChar,
+    BadNumber(&'static str), // why the number isn't 0.00 or [-]1-9 digits . 2 digits
+}
+
+// a lexeme that isn't valid SPL, the lexer skips past it and keeps going
+#[derive(Debug, Clone)]
+pub struct LexError {
+    pub kind: LexErrorKind,
+    pub lexeme: String,
+    pub span: Span,
+}
+
+impl fmt::Display for LexErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            LexErrorKind::NonAsciiComment => "NON ASCII IN COMMENT",
+            LexErrorKind::CommentLength => "COMMENT LENGTH ERROR",
+            LexErrorKind::UnterminatedComment => "UNTERMINATED COMMENT",
+            LexErrorKind::NonAsciiString => "NON ASCII IN STRING",
+            LexErrorKind::StringLength => "STRING LENGTH ERROR",
+            LexErrorKind::UnterminatedString => "UNTERMINATED STRING",
+            LexErrorKind::UnknownWord => "UNKNOWN WORD",
+            LexErrorKind::UnknownChar => "UNKNOWN CHARACTER",
+            LexErrorKind::BadNumber(reason) => return write!(f, "BAD NUMBER - {}", reason),
+        };
+        write!(f, "{}", message)
+    }
 }
 
-use lazy_static::lazy_static;
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "LEX ERROR - {} at {}: {:?}",
+            self.kind, self.span, self.lexeme
+        )
+    }
+}
+
+impl TokenType {
+    // the kind of a lexeme that is a single letter, v and E are operators
+    pub fn letter(c: char) -> Option<Self> {
+        let token_type = match c {
+            'h' => TokenType::Halt,
+            'c' => TokenType::Call,
+            'w' => TokenType::While,
+            'i' => TokenType::If,
+            't' => TokenType::Then,
+            'e' => TokenType::Else,
+            'g' => TokenType::Input,
+            'o' => TokenType::Output,
+            'r' => TokenType::Return,
+            'p' => TokenType::Proc,
+            'a' => TokenType::Add,
+            'm' => TokenType::Mult,
+            'd' => TokenType::Div,
+            'T' => TokenType::True,
+            'F' => TokenType::False,
+            'v' => TokenType::Or,
+            'E' => TokenType::Equal,
+            _ => return None,
+        };
+        Some(token_type)
+    }
 
-lazy_static! {
-    static ref LOGIC_OPS: Vec<String> =
-        vec![String::from("^"), String::from("v"), String::from("!"),];
-    static ref CMPR_OPS: Vec<String> =
-        vec![String::from("E"), String::from("<"), String::from(">"),];
+    // the kind of a punctuation lexeme the lexer put together, None for anything SPL doesn't have
+    pub fn punctuation(lexeme: &str) -> Option<Self> {
+        let token_type = match lexeme {
+            ":=" => TokenType::AssignmentOperator,
+            "^" => TokenType::And,
+            "!" => TokenType::Not,
+            "<" => TokenType::Less,
+            ">" => TokenType::Greater,
+            "," => TokenType::Comma,
+            ";" => TokenType::Semicolon,
+            "{" => TokenType::OpenBrace,
+            "}" => TokenType::CloseBrace,
+            "(" => TokenType::OpenParen,
+            ")" => TokenType::CloseParen,
+            _ => return None,
+        };
+        Some(token_type)
+    }
+
+    // the terminal of the grammar a token of this kind starts with, None for numbers, digits,
+    // strings and comments, the grammar goes through those a char at a time
+    pub fn terminal(&self) -> Option<&'static str> {
+        let terminal = match self {
+            TokenType::End => "$",
+            TokenType::Halt => "h",
+            TokenType::Call => "c",
+            TokenType::While => "w",
+            TokenType::If => "i",
+            TokenType::Then => "t",
+            TokenType::Else => "e",
+            TokenType::Input => "g",
+            TokenType::Output => "o",
+            TokenType::Return => "r",
+            TokenType::Proc | TokenType::Identifier => "p",
+            TokenType::Add => "a",
+            TokenType::Mult => "m",
+            TokenType::Div => "d",
+            TokenType::True => "T",
+            TokenType::False => "F",
+            TokenType::NumVar => "n",
+            TokenType::BoolVar => "b",
+            TokenType::StringVar => "s",
+            TokenType::AssignmentOperator => ":=",
+            TokenType::And => "^",
+            TokenType::Or => "v",
+            TokenType::Not => "!",
+            TokenType::Equal => "E",
+            TokenType::Less => "<",
+            TokenType::Greater => ">",
+            TokenType::Comma => ",",
+            TokenType::Semicolon => ";",
+            TokenType::OpenBrace => "{",
+            TokenType::CloseBrace => "}",
+            TokenType::OpenParen => "(",
+            TokenType::CloseParen => ")",
+            TokenType::NumberLiteral(_)
+            | TokenType::Digits
+            | TokenType::StringLiteral
+            | TokenType::Comment => return None,
+        };
+        Some(terminal)
+    }
 }
 
 impl Token {
-    pub fn new(lexeme: String, lex_type: LexType, position: (usize, usize)) -> Option<Self> {
+    // what the parser looks up in the table for this token, for the kinds without a terminal of
+    // their own that is the next char of what is left of the lexeme
+    pub fn terminal(&self) -> String {
+        match self.token_type.terminal() {
+            Some(terminal) => String::from(terminal),
+            None if self.token_type == TokenType::NumberLiteral(0) => String::from("0.00"),
+            None => self.lexeme.chars().take(1).collect(),
+        }
+    }
+
+    // takes the first char off the lexeme, for when the parser matches a token a char at a time
+    pub fn take_first(&mut self) -> Option<char> {
+        if self.lexeme.is_empty() {
+            return None;
+        }
+        let c = self.lexeme.remove(0);
+        self.span.start += c.len_utf8();
+        if matches!(
+            self.token_type,
+            TokenType::NumVar
+                | TokenType::BoolVar
+                | TokenType::StringVar
+                | TokenType::Identifier
+                | TokenType::NumberLiteral(_)
+        ) {
+            self.token_type = TokenType::Digits;
+        }
+        if c == '\n' || (c == '\r' && !self.lexeme.starts_with('\n')) {
+            self.span.line += 1;
+            self.span.column = 1;
+        } else {
+            self.span.column += 1;
+        }
+        Some(c)
+    }
+
+    pub fn new(lexeme: String, lex_type: LexType, span: Span) -> Result<Self, LexError> {
+        let error = |kind: LexErrorKind, lexeme: String| Err(LexError { kind, lexeme, span });
         match lex_type {
             LexType::Comment => {
-                for c in lexeme.chars() {
-                    if !c.is_ascii() {
-                        panic!("\x1B[31m{}\x1B[0m", "LEX ERROR - NON ASCII IN COMMENT")
-                    }
+                if !lexeme.is_ascii() {
+                    return error(LexErrorKind::NonAsciiComment, lexeme);
                 }
 
                 if lexeme.len() != 17 {
-                    panic!("\x1B[31m{}\x1B[0m", "LEX ERROR - COMMENT LENGTH ERROR")
+                    return error(LexErrorKind::CommentLength, lexeme);
                 }
 
-                Some(Self {
+                Ok(Self {
                     token_type: TokenType::Comment,
                     lexeme,
-                    position,
+                    span,
                 })
             }
             LexType::ShortString => {
-                for c in lexeme.chars() {
-                    if !c.is_ascii() {
-                        panic!("\x1B[31m{}\x1B[0m", "LEX ERROR - NON ASCII IN STRING")
-                    }
+                if !lexeme.is_ascii() {
+                    return error(LexErrorKind::NonAsciiString, lexeme);
                 }
                 if lexeme.len() != 17 {
-                    panic!("\x1B[31m{}\x1B[0m", "LEX ERROR - STRING LENGTH ERROR")
+                    return error(LexErrorKind::StringLength, lexeme);
                 }
-                Some(Self {
+                Ok(Self {
                     token_type: TokenType::StringLiteral,
                     lexeme,
-                    position,
+                    span,
                 })
             }
             LexType::Alpha => {
-                let lex_len = lexeme.clone().len();
-                if lex_len == 1 {
-                    return Some(Token {
-                        token_type: TokenType::Keyword,
-                        lexeme,
-                        position,
-                    });
-                }
+                let mut chars = lexeme.chars();
+                let first = chars.next();
+                let digits = chars.as_str();
+                let token_type = if digits.is_empty() {
+                    first.and_then(TokenType::letter)
+                } else if digits.chars().all(|c| c.is_ascii_digit()) {
+                    match first {
+                        Some('n') => Some(TokenType::NumVar),
+                        Some('b') => Some(TokenType::BoolVar),
+                        Some('s') => Some(TokenType::StringVar),
+                        Some('p') => Some(TokenType::Identifier),
+                        _ => None,
+                    }
+                } else {
+                    None
+                };
 
-                let contains_num = lexeme.chars().any(|c| c.is_numeric());
-                if contains_num {
-                    return Some(Token {
-                        token_type: TokenType::Identifier,
+                match token_type {
+                    Some(token_type) => Ok(Token {
+                        token_type,
                         lexeme,
-                        position,
-                    });
+                        span,
+                    }),
+                    None => error(LexErrorKind::UnknownWord, lexeme),
                 }
-
-                println!("{} - {:?}", lexeme, lex_type);
-                None
             }
-            LexType::Punct => match lexeme.as_str() {
-                ":=" => Some(Self {
-                    token_type: TokenType::AssignmentOperator,
+            LexType::Punct => match TokenType::punctuation(&lexeme) {
+                Some(token_type) => Ok(Self {
+                    token_type,
                     lexeme,
-                    position,
+                    span,
                 }),
-                _ => {
-                    if LOGIC_OPS.contains(&lexeme) {
-                        Some(Self {
-                            token_type: TokenType::LogicalOperator,
-                            lexeme,
-                            position,
-                        })
-                    } else if CMPR_OPS.contains(&lexeme) {
-                        Some(Self {
-                            token_type: TokenType::ComparisionOperator,
-                            lexeme,
-                            position,
-                        })
-                    } else {
-                        Some(Self {
-                            token_type: TokenType::Punctuation,
-                            lexeme,
-                            position,
-                        })
-                    }
-                }
+                None => error(LexErrorKind::UnknownChar, lexeme),
             },
-            LexType::Num => Some(Self {
-                token_type: TokenType::NumberLiteral,
-                lexeme: lexeme,
-                position,
-            }),
-            LexType::End => Some(Self {
+            LexType::Num => match cents(&lexeme) {
+                Ok(cents) => Ok(Self {
+                    token_type: TokenType::NumberLiteral(cents),
+                    lexeme,
+                    span,
+                }),
+                Err(reason) => error(LexErrorKind::BadNumber(reason), lexeme),
+            },
+            LexType::End => Ok(Self {
                 token_type: TokenType::End,
                 lexeme,
-                position,
+                span,
             }),
         }
     }
 }
+
+// SPL numbers are 0.00, or an optional - then an integer part that doesn't start with 0 and
+// exactly two decimals, so they always fit in whole cents
+fn cents(lexeme: &str) -> Result<i64, &'static str> {
+    if lexeme == "0.00" {
+        return Ok(0);
+    }
+    let (negative, unsigned) = match lexeme.strip_prefix('-') {
+        Some(unsigned) => (true, unsigned),
+        None => (false, lexeme),
+    };
+    if unsigned.is_empty() {
+        return Err("missing the digits");
+    }
+    let (int, decimals) = match unsigned.split_once('.') {
+        Some(_) if unsigned.matches('.').count() > 1 => return Err("more than one decimal point"),
+        Some(parts) => parts,
+        None => return Err("missing the decimal point and two decimals"),
+    };
+    if int.is_empty() {
+        return Err("missing the digits before the decimal point");
+    }
+    if !int
+        .chars()
+        .chain(decimals.chars())
+        .all(|c| c.is_ascii_digit())
+    {
+        return Err("only the digits 0-9 can be used");
+    }
+    if int.starts_with('0') {
+        return Err("only 0.00 can start with 0");
+    }
+    if decimals.len() != 2 {
+        return Err("needs exactly two decimals");
+    }
+    let cents = int
+        .parse::<i64>()
+        .ok()
+        .and_then(|int| int.checked_mul(100))
+        .and_then(|cents| cents.checked_add(decimals.parse::<i64>().unwrap()))
+        .ok_or("too big")?;
+    Ok(if negative { -cents } else { cents })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cents_of_valid_numbers() {
+        assert_eq!(cents("0.00"), Ok(0));
+        assert_eq!(cents("1.50"), Ok(150));
+        assert_eq!(cents("-12.07"), Ok(-1207));
+        assert_eq!(cents("100.00"), Ok(10000));
+        assert_eq!(cents("92233720368547758.07"), Ok(i64::MAX));
+        assert_eq!(cents("-92233720368547758.07"), Ok(-i64::MAX));
+    }
+
+    #[test]
+    fn cents_says_what_is_wrong() {
+        assert_eq!(cents("-0.00"), Err("only 0.00 can start with 0"));
+        assert_eq!(
+            cents("007"),
+            Err("missing the decimal point and two decimals")
+        );
+        assert_eq!(cents("007.00"), Err("only 0.00 can start with 0"));
+        assert_eq!(cents("1.5"), Err("needs exactly two decimals"));
+        assert_eq!(cents("1.500"), Err("needs exactly two decimals"));
+        assert_eq!(cents("1.2.3"), Err("more than one decimal point"));
+        assert_eq!(cents("-"), Err("missing the digits"));
+        assert_eq!(
+            cents(".50"),
+            Err("missing the digits before the decimal point")
+        );
+        assert_eq!(cents("1-2.00"), Err("only the digits 0-9 can be used"));
+    }
+
+    #[test]
+    fn cents_overflow_is_too_big() {
+        assert_eq!(cents("92233720368547758.08"), Err("too big"));
+        assert_eq!(cents("99999999999999999999.00"), Err("too big"));
+        assert_eq!(cents("-99999999999999999999.00"), Err("too big"));
+    }
+
+    #[test]
+    fn bad_numbers_are_lex_errors() {
+        let error = Token::new(String::from("1.5"), LexType::Num, Span::default()).unwrap_err();
+        assert_eq!(
+            error.kind,
+            LexErrorKind::BadNumber("needs exactly two decimals")
+        );
+        let token = Token::new(String::from("-3.07"), LexType::Num, Span::default()).unwrap();
+        assert_eq!(token.token_type, TokenType::NumberLiteral(-307));
+    }
+
+    #[test]
+    fn underline_marks_the_span_on_its_line() {
+        let source = "h;\r\nn1:=@;\r\nh";
+        let span = Span {
+            start: 8,
+            end: 9,
+            line: 2,
+            column: 5,
+        };
+        assert_eq!(span.underline(source), "n1:=@;\n    ^");
+    }
+
+    #[test]
+    fn underline_after_a_lone_cr() {
+        let span = Span {
+            start: 3,
+            end: 5,
+            line: 2,
+            column: 2,
+        };
+        assert_eq!(span.underline("h\rabc"), "abc\n ^^");
+    }
+
+    #[test]
+    fn underline_stops_at_the_end_of_the_line() {
+        // an unterminated string runs to the end of the file
+        let source = "s1:=\"abc\nh";
+        let span = Span {
+            start: 4,
+            end: source.len(),
+            line: 1,
+            column: 5,
+        };
+        assert_eq!(span.underline(source), "s1:=\"abc\n    ^^^^");
+    }
+
+    #[test]
+    fn underline_at_the_end_of_the_source() {
+        let span = Span {
+            start: 2,
+            end: 2,
+            line: 1,
+            column: 3,
+        };
+        assert_eq!(span.underline("h;"), "h;\n  ^");
+    }
+}